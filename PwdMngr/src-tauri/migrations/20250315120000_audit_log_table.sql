@@ -0,0 +1,12 @@
+-- Create audit log table (no foreign key, records outlive the account they describe)
+CREATE TABLE IF NOT EXISTS audit_log (
+    id TEXT PRIMARY KEY,
+    user_id TEXT,
+    username TEXT,
+    event TEXT NOT NULL,
+    details TEXT,
+    created_at TEXT NOT NULL
+);
+
+-- Add index for per-user lookups
+CREATE INDEX idx_audit_log_user_id ON audit_log(user_id);
@@ -0,0 +1,4 @@
+-- Add profile metadata to users
+ALTER TABLE users ADD COLUMN display_name TEXT;
+ALTER TABLE users ADD COLUMN email_hint TEXT;
+ALTER TABLE users ADD COLUMN password_hint TEXT;
@@ -0,0 +1,5 @@
+-- Record where an audited action came from (window or cli)
+ALTER TABLE audit_log ADD COLUMN source TEXT;
+
+-- Add index for paging through a user's events
+CREATE INDEX idx_audit_log_user_created ON audit_log(user_id, created_at);
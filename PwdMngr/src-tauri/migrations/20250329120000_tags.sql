@@ -0,0 +1,22 @@
+-- Create tags table (names are unique per user, ignoring case)
+CREATE TABLE IF NOT EXISTS tags (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    name TEXT NOT NULL COLLATE NOCASE,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    UNIQUE (user_id, name)
+);
+
+-- Link entries to their tags
+CREATE TABLE IF NOT EXISTS password_tags (
+    password_id TEXT NOT NULL,
+    tag_id TEXT NOT NULL,
+    PRIMARY KEY (password_id, tag_id),
+    FOREIGN KEY (password_id) REFERENCES passwords(id) ON DELETE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+);
+
+-- Add index for tag lookups
+CREATE INDEX idx_password_tags_tag_id ON password_tags(tag_id);
@@ -0,0 +1,4 @@
+-- Favorite entries can be listed ahead of the rest
+ALTER TABLE passwords ADD COLUMN favorite INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX idx_passwords_favorite ON passwords(user_id, favorite);
@@ -0,0 +1,15 @@
+-- Create custom fields table (values are encrypted like passwords)
+CREATE TABLE IF NOT EXISTS custom_fields (
+    id TEXT PRIMARY KEY,
+    password_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    field_type TEXT NOT NULL,
+    encrypted_value TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (password_id) REFERENCES passwords(id) ON DELETE CASCADE
+);
+
+-- Add index for per-entry lookups
+CREATE INDEX idx_custom_fields_password_id ON custom_fields(password_id, position);
@@ -0,0 +1,6 @@
+-- Entries are logins unless stated otherwise; other types keep their
+-- fields as encrypted JSON in encrypted_data
+ALTER TABLE passwords ADD COLUMN item_type TEXT NOT NULL DEFAULT 'login';
+ALTER TABLE passwords ADD COLUMN encrypted_data TEXT;
+
+CREATE INDEX idx_passwords_item_type ON passwords(user_id, item_type);
@@ -0,0 +1,22 @@
+-- Create attachments table (file names are encrypted like passwords)
+CREATE TABLE IF NOT EXISTS attachments (
+    id TEXT PRIMARY KEY,
+    password_id TEXT NOT NULL,
+    encrypted_name TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    chunk_count INTEGER NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (password_id) REFERENCES passwords(id) ON DELETE CASCADE
+);
+
+-- File contents are split into separately encrypted chunks
+CREATE TABLE IF NOT EXISTS attachment_chunks (
+    attachment_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    data BLOB NOT NULL,
+    PRIMARY KEY (attachment_id, position),
+    FOREIGN KEY (attachment_id) REFERENCES attachments(id) ON DELETE CASCADE
+);
+
+-- Add index for per-entry lookups
+CREATE INDEX idx_attachments_password_id ON attachments(password_id);
@@ -0,0 +1,17 @@
+-- Create entry URLs table, replacing the single website_url of an entry
+CREATE TABLE IF NOT EXISTS entry_urls (
+    password_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    url TEXT NOT NULL,
+    match_mode TEXT NOT NULL DEFAULT 'base_domain',
+    PRIMARY KEY (password_id, position),
+    FOREIGN KEY (password_id) REFERENCES passwords(id) ON DELETE CASCADE
+);
+
+-- Move existing URLs over with the default base domain matching
+INSERT INTO entry_urls (password_id, position, url, match_mode)
+SELECT id, 0, trim(website_url), 'base_domain'
+FROM passwords
+WHERE website_url IS NOT NULL AND trim(website_url) != '';
+
+ALTER TABLE passwords DROP COLUMN website_url;
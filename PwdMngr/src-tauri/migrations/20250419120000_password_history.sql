@@ -0,0 +1,11 @@
+-- Create password history table (old passwords stay encrypted)
+CREATE TABLE IF NOT EXISTS password_history (
+    id TEXT PRIMARY KEY,
+    password_id TEXT NOT NULL,
+    encrypted_password TEXT NOT NULL,
+    replaced_at TEXT NOT NULL,
+    FOREIGN KEY (password_id) REFERENCES passwords(id) ON DELETE CASCADE
+);
+
+-- Add index for per-entry lookups
+CREATE INDEX idx_password_history_password_id ON password_history(password_id, replaced_at);
@@ -0,0 +1,5 @@
+-- Deleted entries stay in the trash until they are purged
+ALTER TABLE passwords ADD COLUMN deleted_at TEXT;
+
+-- Add index for purging old entries
+CREATE INDEX idx_passwords_deleted_at ON passwords(deleted_at);
@@ -0,0 +1,11 @@
+-- Create entry revisions table (snapshots are encrypted like passwords)
+CREATE TABLE IF NOT EXISTS entry_revisions (
+    id TEXT PRIMARY KEY,
+    password_id TEXT NOT NULL,
+    number INTEGER NOT NULL,
+    encrypted_snapshot TEXT NOT NULL,
+    changed_fields TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    UNIQUE (password_id, number),
+    FOREIGN KEY (password_id) REFERENCES passwords(id) ON DELETE CASCADE
+);
@@ -0,0 +1,2 @@
+-- One-time password secret of an entry, encrypted like passwords
+ALTER TABLE passwords ADD COLUMN encrypted_totp TEXT;
@@ -0,0 +1,10 @@
+-- Optional expiry date and rotation interval per entry
+ALTER TABLE passwords ADD COLUMN expires_on TEXT;
+ALTER TABLE passwords ADD COLUMN rotation_days INTEGER;
+
+-- When the password last changed; entries that were never changed fall back to created_at
+ALTER TABLE passwords ADD COLUMN password_changed_at TEXT;
+
+UPDATE passwords SET password_changed_at = (
+    SELECT MAX(replaced_at) FROM password_history WHERE password_history.password_id = passwords.id
+);
@@ -0,0 +1,440 @@
+use crate::crypto;
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use std::path::Path;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use uuid::Uuid;
+
+/// Files are encrypted in pieces of this size, so only one piece is in memory at a time.
+const CHUNK_SIZE: usize = 512 * 1024;
+
+/// Each chunk is bound to its attachment, its position and the number of chunks,
+/// so chunks can't be reordered, swapped between attachments or dropped from
+/// the end without failing to decrypt.
+fn chunk_aad(attachment_id: &str, position: i64, chunk_count: i64) -> Vec<u8> {
+    format!("{}:{}:{}", attachment_id, position, chunk_count).into_bytes()
+}
+
+/// Reads `file` to the end and stores it as encrypted chunks of `attachment_id`.
+/// Fails once more than `max_size` bytes have been read. Returns the size and
+/// the number of chunks.
+pub async fn store_chunks(
+    tx: &mut Transaction<'_, Sqlite>,
+    attachment_id: &str,
+    file: &mut File,
+    max_size: u64,
+    enc_key: &str,
+) -> Result<(u64, i64), String> {
+    let expected_size = file
+        .metadata()
+        .await
+        .map_err(|e| format!("Failed to read file: {}", e))?
+        .len();
+    let chunk_count = expected_size.div_ceil(CHUNK_SIZE as u64) as i64;
+
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut size = 0u64;
+    let mut position = 0i64;
+
+    loop {
+        let mut filled = 0;
+        while filled < CHUNK_SIZE {
+            let read = file
+                .read(&mut buffer[filled..])
+                .await
+                .map_err(|e| format!("Failed to read file: {}", e))?;
+            if read == 0 {
+                break;
+            }
+            filled += read;
+        }
+
+        if filled == 0 {
+            break;
+        }
+
+        size += filled as u64;
+        if size > max_size {
+            return Err("File is too large to attach".into());
+        }
+        if position >= chunk_count {
+            return Err("File changed while it was being attached".into());
+        }
+
+        let data = crypto::encrypt_bytes(
+            &buffer[..filled],
+            &chunk_aad(attachment_id, position, chunk_count),
+            enc_key,
+        )
+        .map_err(|e| format!("Failed to encrypt attachment: {}", e))?;
+
+        sqlx::query(
+            "INSERT INTO attachment_chunks (attachment_id, position, data) VALUES (?, ?, ?)",
+        )
+        .bind(attachment_id)
+        .bind(position)
+        .bind(&data)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to save attachment: {}", e))?;
+
+        position += 1;
+
+        if filled < CHUNK_SIZE {
+            break;
+        }
+    }
+
+    if size != expected_size || position != chunk_count {
+        return Err("File changed while it was being attached".into());
+    }
+
+    Ok((size, chunk_count))
+}
+
+/// Decrypts the chunks of an attachment one at a time into a file at `destination`.
+/// The file is written under a temporary name next to it and only replaces
+/// `destination` once complete, so a failed download leaves an existing file alone.
+pub async fn write_to_file(
+    db: &SqlitePool,
+    attachment_id: &str,
+    chunk_count: i64,
+    destination: &Path,
+    enc_key: &str,
+) -> Result<(), String> {
+    let file_name = destination
+        .file_name()
+        .ok_or("Destination must be a file")?
+        .to_string_lossy();
+    let partial = destination.with_file_name(format!(".{}.{}.partial", file_name, Uuid::new_v4()));
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&partial)
+        .await
+        .map_err(|e| format!("Failed to create {}: {}", destination.display(), e))?;
+
+    let result = async {
+        for position in 0..chunk_count {
+            let data = sqlx::query_scalar::<_, Vec<u8>>(
+                "SELECT data FROM attachment_chunks WHERE attachment_id = ? AND position = ?",
+            )
+            .bind(attachment_id)
+            .bind(position)
+            .fetch_optional(db)
+            .await
+            .map_err(|e| format!("Failed to fetch attachment: {}", e))?
+            .ok_or("Attachment is incomplete")?;
+
+            let plaintext = crypto::decrypt_bytes(
+                &data,
+                &chunk_aad(attachment_id, position, chunk_count),
+                enc_key,
+            )
+            .map_err(|e| format!("Failed to decrypt attachment: {}", e))?;
+
+            file.write_all(&plaintext)
+                .await
+                .map_err(|e| format!("Failed to write file: {}", e))?;
+        }
+
+        file.flush()
+            .await
+            .map_err(|e| format!("Failed to write file: {}", e))?;
+        drop(file);
+
+        tokio::fs::rename(&partial, destination)
+            .await
+            .map_err(|e| format!("Failed to save {}: {}", destination.display(), e))
+    }
+    .await;
+
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&partial).await;
+    }
+
+    result
+}
+
+/// Re-encrypts the names and contents of all attachments of a user, one chunk at a time.
+pub async fn reencrypt_attachments(
+    tx: &mut Transaction<'_, Sqlite>,
+    user_id: &str,
+    old_key: &str,
+    new_key: &str,
+) -> Result<(), String> {
+    let attachments = sqlx::query_as::<_, (String, String, i64)>(
+        "SELECT a.id, a.encrypted_name, a.chunk_count
+        FROM attachments a
+        JOIN passwords p ON p.id = a.password_id
+        WHERE p.user_id = ?",
+    )
+    .bind(user_id)
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(|e| format!("Failed to fetch attachments: {}", e))?;
+
+    for (attachment_id, encrypted_name, chunk_count) in attachments {
+        let name = crypto::decrypt(&encrypted_name, old_key)
+            .map_err(|e| format!("Failed to decrypt: {}", e))?;
+        let encrypted_name = crypto::encrypt(&name, user_id, new_key)
+            .map_err(|e| format!("Failed to encrypt: {}", e))?;
+
+        sqlx::query("UPDATE attachments SET encrypted_name = ? WHERE id = ?")
+            .bind(&encrypted_name)
+            .bind(&attachment_id)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| format!("Failed to update attachment: {}", e))?;
+
+        for position in 0..chunk_count {
+            let aad = chunk_aad(&attachment_id, position, chunk_count);
+
+            let data = sqlx::query_scalar::<_, Vec<u8>>(
+                "SELECT data FROM attachment_chunks WHERE attachment_id = ? AND position = ?",
+            )
+            .bind(&attachment_id)
+            .bind(position)
+            .fetch_one(&mut **tx)
+            .await
+            .map_err(|e| format!("Failed to fetch attachment: {}", e))?;
+
+            let plaintext = crypto::decrypt_bytes(&data, &aad, old_key)
+                .map_err(|e| format!("Failed to decrypt: {}", e))?;
+            let data = crypto::encrypt_bytes(&plaintext, &aad, new_key)
+                .map_err(|e| format!("Failed to encrypt: {}", e))?;
+
+            sqlx::query(
+                "UPDATE attachment_chunks SET data = ? WHERE attachment_id = ? AND position = ?",
+            )
+            .bind(&data)
+            .bind(&attachment_id)
+            .bind(position)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| format!("Failed to update attachment: {}", e))?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use std::path::PathBuf;
+
+    struct Fixture {
+        db: SqlitePool,
+        dir: PathBuf,
+        enc_key: String,
+    }
+
+    impl Fixture {
+        async fn new() -> Self {
+            let db = SqlitePoolOptions::new()
+                .max_connections(1)
+                .connect("sqlite::memory:")
+                .await
+                .unwrap();
+            sqlx::query(
+                "CREATE TABLE attachment_chunks (
+                    attachment_id TEXT NOT NULL,
+                    position INTEGER NOT NULL,
+                    data BLOB NOT NULL,
+                    PRIMARY KEY (attachment_id, position)
+                )",
+            )
+            .execute(&db)
+            .await
+            .unwrap();
+
+            let dir = std::env::temp_dir().join(format!("attachments-{}", Uuid::new_v4()));
+            tokio::fs::create_dir_all(&dir).await.unwrap();
+
+            Self {
+                db,
+                dir,
+                enc_key: crypto::generate_encryption_key("correct horse battery staple").unwrap(),
+            }
+        }
+
+        async fn store(
+            &self,
+            attachment_id: &str,
+            contents: &[u8],
+            max_size: u64,
+        ) -> Result<(u64, i64), String> {
+            let path = self.dir.join(Uuid::new_v4().to_string());
+            tokio::fs::write(&path, contents).await.unwrap();
+            let mut file = File::open(&path).await.unwrap();
+
+            let mut tx = self.db.begin().await.unwrap();
+            let result =
+                store_chunks(&mut tx, attachment_id, &mut file, max_size, &self.enc_key).await;
+            tx.commit().await.unwrap();
+            result
+        }
+
+        async fn read(&self, attachment_id: &str, chunk_count: i64) -> Result<Vec<u8>, String> {
+            let destination = self.dir.join("download");
+            let _ = tokio::fs::remove_file(&destination).await;
+
+            write_to_file(
+                &self.db,
+                attachment_id,
+                chunk_count,
+                &destination,
+                &self.enc_key,
+            )
+            .await?;
+            Ok(tokio::fs::read(&destination).await.unwrap())
+        }
+
+        async fn leftover_files(&self) -> usize {
+            let mut entries = tokio::fs::read_dir(&self.dir).await.unwrap();
+            let mut count = 0;
+            while let Some(entry) = entries.next_entry().await.unwrap() {
+                if entry.file_name().to_string_lossy().ends_with(".partial") {
+                    count += 1;
+                }
+            }
+            count
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    /// Two full chunks and a partial one.
+    fn contents() -> Vec<u8> {
+        (0..CHUNK_SIZE * 2 + 1000)
+            .map(|i| (i % 251) as u8)
+            .collect()
+    }
+
+    #[test]
+    fn aad_binds_id_position_and_count() {
+        assert_eq!(chunk_aad("a1", 2, 5), b"a1:2:5");
+        assert_ne!(chunk_aad("a1", 0, 2), chunk_aad("a2", 0, 2));
+        assert_ne!(chunk_aad("a1", 0, 2), chunk_aad("a1", 1, 2));
+        assert_ne!(chunk_aad("a1", 0, 2), chunk_aad("a1", 0, 3));
+    }
+
+    #[tokio::test]
+    async fn round_trips_chunked_file() {
+        let fixture = Fixture::new().await;
+        let contents = contents();
+
+        let (size, chunk_count) = fixture.store("a1", &contents, u64::MAX).await.unwrap();
+        assert_eq!(size, contents.len() as u64);
+        assert_eq!(chunk_count, 3);
+
+        let stored: Vec<Vec<u8>> = sqlx::query_scalar(
+            "SELECT data FROM attachment_chunks WHERE attachment_id = 'a1' ORDER BY position",
+        )
+        .fetch_all(&fixture.db)
+        .await
+        .unwrap();
+        assert_eq!(stored.len(), 3);
+        assert!(stored[0]
+            .windows(64)
+            .all(|window| window != &contents[..64]));
+
+        assert_eq!(fixture.read("a1", chunk_count).await.unwrap(), contents);
+    }
+
+    #[tokio::test]
+    async fn round_trips_empty_and_exact_chunk_files() {
+        let fixture = Fixture::new().await;
+
+        assert_eq!(fixture.store("empty", &[], u64::MAX).await.unwrap(), (0, 0));
+        assert!(fixture.read("empty", 0).await.unwrap().is_empty());
+
+        let contents = vec![7u8; CHUNK_SIZE];
+        assert_eq!(
+            fixture.store("exact", &contents, u64::MAX).await.unwrap(),
+            (CHUNK_SIZE as u64, 1)
+        );
+        assert_eq!(fixture.read("exact", 1).await.unwrap(), contents);
+    }
+
+    #[tokio::test]
+    async fn enforces_size_limit() {
+        let fixture = Fixture::new().await;
+        let contents = contents();
+        let size = contents.len() as u64;
+
+        assert_eq!(
+            fixture.store("a1", &contents, size - 1).await.unwrap_err(),
+            "File is too large to attach"
+        );
+        assert_eq!(
+            fixture.store("a2", &contents, size).await.unwrap(),
+            (size, 3)
+        );
+    }
+
+    #[tokio::test]
+    async fn reordered_chunks_fail_to_decrypt() {
+        let fixture = Fixture::new().await;
+        let (_, chunk_count) = fixture.store("a1", &contents(), u64::MAX).await.unwrap();
+
+        for (from, to) in [(0, -1), (1, 0), (-1, 1)] {
+            sqlx::query(
+                "UPDATE attachment_chunks SET position = ? WHERE attachment_id = 'a1' AND position = ?",
+            )
+            .bind(to)
+            .bind(from)
+            .execute(&fixture.db)
+            .await
+            .unwrap();
+        }
+
+        let err = fixture.read("a1", chunk_count).await.unwrap_err();
+        assert!(err.starts_with("Failed to decrypt attachment"), "{}", err);
+        assert!(!fixture.dir.join("download").exists());
+        assert_eq!(fixture.leftover_files().await, 0);
+    }
+
+    #[tokio::test]
+    async fn truncated_chunks_fail_to_decrypt() {
+        let fixture = Fixture::new().await;
+        let (_, chunk_count) = fixture.store("a1", &contents(), u64::MAX).await.unwrap();
+
+        sqlx::query("DELETE FROM attachment_chunks WHERE attachment_id = 'a1' AND position = ?")
+            .bind(chunk_count - 1)
+            .execute(&fixture.db)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            fixture.read("a1", chunk_count).await.unwrap_err(),
+            "Attachment is incomplete"
+        );
+
+        // Claiming the shorter list is complete doesn't help, the count is bound too
+        let err = fixture.read("a1", chunk_count - 1).await.unwrap_err();
+        assert!(err.starts_with("Failed to decrypt attachment"), "{}", err);
+        assert_eq!(fixture.leftover_files().await, 0);
+    }
+
+    #[tokio::test]
+    async fn chunks_of_another_attachment_fail_to_decrypt() {
+        let fixture = Fixture::new().await;
+        let (_, chunk_count) = fixture.store("a1", &contents(), u64::MAX).await.unwrap();
+
+        sqlx::query("UPDATE attachment_chunks SET attachment_id = 'a2' WHERE attachment_id = 'a1'")
+            .execute(&fixture.db)
+            .await
+            .unwrap();
+
+        let err = fixture.read("a2", chunk_count).await.unwrap_err();
+        assert!(err.starts_with("Failed to decrypt attachment"), "{}", err);
+    }
+}
@@ -0,0 +1,40 @@
+use crate::session::SessionSource;
+use chrono::Utc;
+use sqlx::{Executor, Sqlite};
+use uuid::Uuid;
+
+pub const LOGIN_SUCCEEDED: &str = "login_succeeded";
+pub const LOGIN_FAILED: &str = "login_failed";
+pub const LOGOUT: &str = "logout";
+pub const VAULT_LOCKED: &str = "vault_locked";
+pub const VAULT_UNLOCKED: &str = "vault_unlocked";
+pub const MASTER_PASSWORD_CHANGED: &str = "master_password_changed";
+pub const USERNAME_CHANGED: &str = "username_changed";
+pub const ACCOUNT_DELETED: &str = "account_deleted";
+
+pub async fn record_event<'e, E>(
+    executor: E,
+    user_id: Option<&str>,
+    username: Option<&str>,
+    event: &str,
+    source: Option<SessionSource>,
+    details: Option<&str>,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    sqlx::query(
+        "INSERT INTO audit_log (id, user_id, username, event, source, details, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(user_id)
+    .bind(username)
+    .bind(event)
+    .bind(source.map(SessionSource::as_str))
+    .bind(details)
+    .bind(Utc::now())
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
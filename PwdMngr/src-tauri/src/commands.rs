@@ -1,20 +1,36 @@
-use crate::{crypto, models::PasswordRecord, models::User, user_state, DatabasePool, UserState};
-use chrono::Utc;
+use crate::{
+    audit, config::AppConfig, crypto, custom_fields, db, entry_urls, error::CommandError, items::ItemData,
+    models::CustomField, models::EntryFilter, models::EntryUrl, models::ItemType, models::PasswordRecord, models::TagMatch, models::SecurityEvent,
+    models::User, otp::Otp, session, session::SessionScope, session::SessionSource, DatabasePool, SessionRegistry,
+};
+use chrono::{DateTime, Utc};
 use serde_json::{json, Value as JsonValue};
-use tauri::State;
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use tauri::{State, Window};
 use uuid::Uuid;
 
+pub mod attachments;
+pub mod folders;
+pub mod history;
+pub mod items;
+pub mod revisions;
+pub mod rotation;
+pub mod ssh_keys;
+pub mod tags;
+pub mod totp;
+pub mod trash;
+pub mod vaults;
+
 #[tauri::command]
 pub async fn register_user(
     pool: State<'_, DatabasePool>,
-    user_state: State<'_, UserState>,
+    sessions: State<'_, SessionRegistry>,
+    config: State<'_, AppConfig>,
     username: String,
     password: String,
     confirm_password: String,
-) -> Result<JsonValue, String> {
-    if user_state::require_no_authentication(&user_state).is_err() {
-        return Err("Already authenticated".into());
-    }
+) -> Result<JsonValue, CommandError> {
+    let (vault_path, db) = pool.active()?;
 
     if username.trim().is_empty() {
         return Err("Username cannot be empty".into());
@@ -28,9 +44,14 @@ pub async fn register_user(
         return Err("Passwords do not match".into());
     }
 
+    let violations = config.password_policy.check(&password, &[&username]);
+    if !violations.is_empty() {
+        return Err(CommandError::password_policy(violations));
+    }
+
     let existing_user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE username = ?")
         .bind(&username)
-        .fetch_optional(&*pool.0)
+        .fetch_optional(&*db)
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
@@ -50,31 +71,53 @@ pub async fn register_user(
         .bind(&password_hash)
         .bind(now)
         .bind(now)
-        .execute(&*pool.0)
+        .execute(&*db)
         .await
         .map_err(|e| format!("Failed to create user: {}", e))?;
 
     let encrypted_key = crypto::generate_encryption_key(&password)
         .map_err(|e| format!("Failed to generate encryption key: {}", e))?;
 
-    user_state::set_current_user(&user_state, user_id);
+    audit::record_event(
+        &*db,
+        Some(&user_id),
+        Some(&username),
+        audit::LOGIN_SUCCEEDED,
+        Some(SessionSource::Window),
+        Some("Registered account"),
+    )
+    .await
+    .map_err(|e| format!("Failed to write audit record: {}", e))?;
+
+    let session_token = session::create_session(
+        &sessions,
+        vault_path,
+        user_id,
+        encrypted_key,
+        SessionScope::Full,
+        SessionSource::Window,
+        None,
+    )?;
 
     Ok(json!({
-        "encKey": encrypted_key,
+        "sessionToken": session_token,
         "message": "User successfully registered!"
     }))
 }
 
 #[tauri::command]
 pub async fn login_user(
+    window: Window,
     pool: State<'_, DatabasePool>,
-    user_state: State<'_, UserState>,
+    sessions: State<'_, SessionRegistry>,
     username: String,
     password: String,
+    scope: Option<SessionScope>,
+    source: Option<SessionSource>,
 ) -> Result<JsonValue, String> {
-    if user_state::require_no_authentication(&user_state).is_err() {
-        return Err("Already authenticated".into());
-    }
+    let (vault_path, db) = pool.active()?;
+
+    let source = source.unwrap_or(SessionSource::Window);
 
     if username.trim().is_empty() {
         return Err("Username cannot be empty".into());
@@ -84,56 +127,779 @@ pub async fn login_user(
     }
 
     let existing_user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE username = ?")
-        .bind(&username)
-        .fetch_optional(&*pool.0)
+        .bind(&username)
+        .fetch_optional(&*db)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    let existing_user = match existing_user {
+        Some(user) => user,
+        None => {
+            audit::record_event(
+                &*db,
+                None,
+                Some(&username),
+                audit::LOGIN_FAILED,
+                Some(source),
+                Some("Unknown username"),
+            )
+            .await
+            .map_err(|e| format!("Failed to write audit record: {}", e))?;
+
+            return Err("Username does not exist".into());
+        }
+    };
+
+    let is_correct_pwd = crypto::verify_password(&password, &existing_user.password_hash)
+        .map_err(|e| format!("Password verification error: {}", e))?;
+
+    if !is_correct_pwd {
+        audit::record_event(
+            &*db,
+            Some(&existing_user.id),
+            Some(&existing_user.username),
+            audit::LOGIN_FAILED,
+            Some(source),
+            Some("Wrong password"),
+        )
+        .await
+        .map_err(|e| format!("Failed to write audit record: {}", e))?;
+
+        return Err("Password does not match!".into());
+    }
+
+    let encrypted_key = crypto::generate_encryption_key(&password)
+        .map_err(|e| format!("Failed to generate encryption key: {}", e))?;
+
+    let previous_login_at = sqlx::query_scalar::<_, Option<DateTime<Utc>>>(
+        "SELECT MAX(created_at) FROM audit_log WHERE user_id = ? AND event = ?",
+    )
+    .bind(&existing_user.id)
+    .bind(audit::LOGIN_SUCCEEDED)
+    .fetch_one(&*db)
+    .await
+    .map_err(|e| format!("Failed to fetch login history: {}", e))?;
+
+    let failed_attempts = count_failed_logins_since(&db, &existing_user.id, previous_login_at).await?;
+
+    audit::record_event(
+        &*db,
+        Some(&existing_user.id),
+        Some(&existing_user.username),
+        audit::LOGIN_SUCCEEDED,
+        Some(source),
+        None,
+    )
+    .await
+    .map_err(|e| format!("Failed to write audit record: {}", e))?;
+
+    crate::rotation::notify_overdue(&window, &*db, &existing_user.id, &encrypted_key).await?;
+
+    let scope = scope.unwrap_or(SessionScope::Full);
+    let session_token = session::create_session(
+        &sessions,
+        vault_path,
+        existing_user.id.clone(),
+        encrypted_key,
+        scope,
+        source,
+        previous_login_at,
+    )?;
+
+    Ok(json!({
+        "sessionToken": session_token,
+        "scope": scope,
+        "failed_attempts_since_last_login": failed_attempts,
+        "message": "Login successful!"
+    }))
+}
+
+#[tauri::command]
+pub async fn logout_user(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+) -> Result<JsonValue, String> {
+    if let Some(session) = session::end_session(&sessions, &session_token) {
+        let db = pool.for_session(&session)?;
+        audit::record_event(
+            &*db,
+            Some(&session.user_id),
+            None,
+            audit::LOGOUT,
+            Some(session.source),
+            None,
+        )
+        .await
+        .map_err(|e| format!("Failed to write audit record: {}", e))?;
+    }
+
+    Ok(json!({
+        "message": "Logout successful!"
+    }))
+}
+
+#[tauri::command]
+pub async fn lock_vault(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+) -> Result<JsonValue, String> {
+    let session = session::lock_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    audit::record_event(
+        &*db,
+        Some(&session.user_id),
+        None,
+        audit::VAULT_LOCKED,
+        Some(session.source),
+        None,
+    )
+    .await
+    .map_err(|e| format!("Failed to write audit record: {}", e))?;
+
+    Ok(json!({
+        "message": "Vault locked!"
+    }))
+}
+
+#[tauri::command]
+pub async fn unlock_vault(
+    window: Window,
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    password: String,
+) -> Result<JsonValue, String> {
+    let session = session::find_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    if password.trim().is_empty() {
+        return Err("Password cannot be empty".into());
+    }
+
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = ?")
+        .bind(&session.user_id)
+        .fetch_optional(&*db)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or("User not found")?;
+
+    let is_correct_pwd = crypto::verify_password(&password, &user.password_hash)
+        .map_err(|e| format!("Password verification error: {}", e))?;
+
+    if !is_correct_pwd {
+        audit::record_event(
+            &*db,
+            Some(&user.id),
+            Some(&user.username),
+            audit::LOGIN_FAILED,
+            Some(session.source),
+            Some("Wrong password on unlock"),
+        )
+        .await
+        .map_err(|e| format!("Failed to write audit record: {}", e))?;
+
+        return Err("Password does not match!".into());
+    }
+
+    let encrypted_key = crypto::generate_encryption_key(&password)
+        .map_err(|e| format!("Failed to generate encryption key: {}", e))?;
+
+    crate::rotation::notify_overdue(&window, &*db, &user.id, &encrypted_key).await?;
+
+    session::unlock_session(&sessions, &session_token, encrypted_key)?;
+
+    audit::record_event(
+        &*db,
+        Some(&user.id),
+        Some(&user.username),
+        audit::VAULT_UNLOCKED,
+        Some(session.source),
+        None,
+    )
+    .await
+    .map_err(|e| format!("Failed to write audit record: {}", e))?;
+
+    Ok(json!({
+        "message": "Vault unlocked!"
+    }))
+}
+
+async fn count_failed_logins_since(
+    pool: &SqlitePool,
+    user_id: &str,
+    since: Option<DateTime<Utc>>,
+) -> Result<i64, String> {
+    sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM audit_log WHERE user_id = ? AND event = ? AND (? IS NULL OR created_at > ?)",
+    )
+    .bind(user_id)
+    .bind(audit::LOGIN_FAILED)
+    .bind(since)
+    .bind(since)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("Failed to count failed logins: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_security_events(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    page: i32,
+) -> Result<JsonValue, String> {
+    let session = session::require_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let user_id = session.user_id;
+    let page_size = 20;
+    let offset = (page - 1) * page_size;
+
+    let total_count =
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM audit_log WHERE user_id = ?")
+            .bind(&user_id)
+            .fetch_one(&*db)
+            .await
+            .map_err(|e| format!("Failed to count security events: {}", e))?;
+
+    let events = sqlx::query_as::<_, SecurityEvent>(
+        "
+        SELECT id, event, source, details, created_at
+        FROM audit_log
+        WHERE user_id = ?
+        ORDER BY created_at DESC
+        LIMIT ? OFFSET ?
+    ",
+    )
+    .bind(&user_id)
+    .bind(page_size)
+    .bind(offset)
+    .fetch_all(&*db)
+    .await
+    .map_err(|e| format!("Failed to fetch security events: {}", e))?;
+
+    let failed_since_last_login =
+        count_failed_logins_since(&db, &user_id, session.previous_login_at).await?;
+
+    let total_pages = (total_count as f64 / page_size as f64).ceil() as i32;
+
+    // Failed attempts after the previous successful login are ones the user has not seen yet
+    let event_list: Vec<JsonValue> = events
+        .into_iter()
+        .map(|event| {
+            let highlight = event.event == audit::LOGIN_FAILED
+                && session
+                    .previous_login_at
+                    .is_none_or(|previous| event.created_at > previous);
+
+            json!({
+                "id": event.id,
+                "event": event.event,
+                "source": event.source,
+                "details": event.details,
+                "created_at": event.created_at.to_rfc3339(),
+                "highlight": highlight
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "events": event_list,
+        "total": total_count,
+        "page": page,
+        "total_pages": total_pages,
+        "failed_since_last_login": failed_since_last_login
+    }))
+}
+
+#[tauri::command]
+pub async fn get_session(
+    sessions: State<'_, SessionRegistry>,
+    session_token: String,
+) -> Result<JsonValue, String> {
+    let session = session::find_session(&sessions, &session_token)?;
+
+    Ok(json!({
+        "user_id": session.user_id,
+        "locked": session.locked,
+        "scope": session.scope,
+        "created_at": session.created_at.to_rfc3339(),
+        "last_activity": session.last_activity.to_rfc3339()
+    }))
+}
+
+#[tauri::command]
+pub async fn verify_master_password(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    password: String,
+) -> Result<JsonValue, String> {
+    let session = session::require_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    if password.trim().is_empty() {
+        return Err("Password cannot be empty".into());
+    }
+
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = ?")
+        .bind(&session.user_id)
+        .fetch_optional(&*db)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or("User not found")?;
+
+    let is_correct_pwd = crypto::verify_password(&password, &user.password_hash)
+        .map_err(|e| format!("Password verification error: {}", e))?;
+
+    if !is_correct_pwd {
+        return Err("Password does not match!".into());
+    }
+
+    session::mark_verified(&sessions, &session_token)?;
+
+    Ok(json!({
+        "message": "Master password verified!"
+    }))
+}
+
+#[tauri::command]
+pub async fn change_master_password(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    config: State<'_, AppConfig>,
+    session_token: String,
+    current_password: String,
+    new_password: String,
+    confirm_password: String,
+) -> Result<JsonValue, CommandError> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    if current_password.trim().is_empty() || new_password.trim().is_empty() {
+        return Err("Password cannot be empty".into());
+    }
+
+    if new_password != confirm_password {
+        return Err("Passwords do not match".into());
+    }
+
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = ?")
+        .bind(&session.user_id)
+        .fetch_optional(&*db)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or("User not found")?;
+
+    let is_correct_pwd = crypto::verify_password(&current_password, &user.password_hash)
+        .map_err(|e| format!("Password verification error: {}", e))?;
+
+    if !is_correct_pwd {
+        return Err("Password does not match!".into());
+    }
+
+    let violations = config.password_policy.check(&new_password, &[&user.username]);
+    if !violations.is_empty() {
+        return Err(CommandError::password_policy(violations));
+    }
+
+    let password_hash = crypto::hash_password(&new_password)
+        .map_err(|e| format!("Password hashing error: {}", e))?;
+
+    let new_key = crypto::generate_encryption_key(&new_password)
+        .map_err(|e| format!("Failed to generate encryption key: {}", e))?;
+
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    reencrypt_user_data(&mut tx, &user.id, &session.enc_key, &new_key).await?;
+
+    sqlx::query("UPDATE users SET password_hash = ?, updated_at = ? WHERE id = ?")
+        .bind(&password_hash)
+        .bind(Utc::now())
+        .bind(&user.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to update password: {}", e))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to change master password: {}", e))?;
+
+    session::replace_user_key(&sessions, &user.id, &new_key);
+
+    audit::record_event(
+        &*db,
+        Some(&user.id),
+        Some(&user.username),
+        audit::MASTER_PASSWORD_CHANGED,
+        Some(session.source),
+        None,
+    )
+    .await
+    .map_err(|e| format!("Failed to write audit record: {}", e))?;
+
+    Ok(json!({
+        "message": "Master password successfully changed!"
+    }))
+}
+
+/// Re-encrypts every encrypted column owned by the user with a new key.
+async fn reencrypt_user_data(
+    tx: &mut Transaction<'_, Sqlite>,
+    user_id: &str,
+    old_key: &str,
+    new_key: &str,
+) -> Result<(), String> {
+    let passwords = sqlx::query_as::<_, PasswordRecord>(
+        "SELECT id, website, encrypted_username, encrypted_password, notes, item_type, encrypted_data, encrypted_totp, folder_id, favorite, updated_at
+        FROM passwords
+        WHERE user_id = ?",
+    )
+    .bind(user_id)
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(|e| format!("Failed to fetch passwords: {}", e))?;
+
+    for password in passwords {
+        // Other entry types leave these empty, which reencrypt keeps as is
+        let encrypted_username = reencrypt(&password.encrypted_username, user_id, old_key, new_key)?;
+        let encrypted_password = reencrypt(&password.encrypted_password, user_id, old_key, new_key)?;
+        let encrypted_data = password
+            .encrypted_data
+            .as_deref()
+            .map(|data| reencrypt(data, user_id, old_key, new_key))
+            .transpose()?;
+        let encrypted_totp = password
+            .encrypted_totp
+            .as_deref()
+            .map(|totp| reencrypt(totp, user_id, old_key, new_key))
+            .transpose()?;
+
+        sqlx::query("UPDATE passwords SET encrypted_username = ?, encrypted_password = ?, encrypted_data = ?, encrypted_totp = ? WHERE id = ?")
+            .bind(&encrypted_username)
+            .bind(&encrypted_password)
+            .bind(&encrypted_data)
+            .bind(&encrypted_totp)
+            .bind(&password.id)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| format!("Failed to update password: {}", e))?;
+    }
+
+    let fields = sqlx::query_as::<_, (String, String)>(
+        "SELECT cf.id, cf.encrypted_value
+        FROM custom_fields cf
+        JOIN passwords p ON p.id = cf.password_id
+        WHERE p.user_id = ?",
+    )
+    .bind(user_id)
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(|e| format!("Failed to fetch custom fields: {}", e))?;
+
+    for (field_id, encrypted_value) in fields {
+        let encrypted_value = reencrypt(&encrypted_value, user_id, old_key, new_key)?;
+
+        sqlx::query("UPDATE custom_fields SET encrypted_value = ? WHERE id = ?")
+            .bind(&encrypted_value)
+            .bind(&field_id)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| format!("Failed to update custom field: {}", e))?;
+    }
+
+    let history = sqlx::query_as::<_, (String, String)>(
+        "SELECT h.id, h.encrypted_password
+        FROM password_history h
+        JOIN passwords p ON p.id = h.password_id
+        WHERE p.user_id = ?",
+    )
+    .bind(user_id)
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(|e| format!("Failed to fetch password history: {}", e))?;
+
+    for (history_id, encrypted_password) in history {
+        let encrypted_password = reencrypt(&encrypted_password, user_id, old_key, new_key)?;
+
+        sqlx::query("UPDATE password_history SET encrypted_password = ? WHERE id = ?")
+            .bind(&encrypted_password)
+            .bind(&history_id)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| format!("Failed to update password history: {}", e))?;
+    }
+
+    let revisions = sqlx::query_as::<_, (String, String)>(
+        "SELECT r.id, r.encrypted_snapshot
+        FROM entry_revisions r
+        JOIN passwords p ON p.id = r.password_id
+        WHERE p.user_id = ?",
+    )
+    .bind(user_id)
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(|e| format!("Failed to fetch revisions: {}", e))?;
+
+    for (revision_id, encrypted_snapshot) in revisions {
+        let encrypted_snapshot = reencrypt(&encrypted_snapshot, user_id, old_key, new_key)?;
+
+        sqlx::query("UPDATE entry_revisions SET encrypted_snapshot = ? WHERE id = ?")
+            .bind(&encrypted_snapshot)
+            .bind(&revision_id)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| format!("Failed to update revision: {}", e))?;
+    }
+
+    crate::attachments::reencrypt_attachments(tx, user_id, old_key, new_key).await?;
+
+    Ok(())
+}
+
+/// Entries other than logins leave the login columns empty rather than encrypted.
+fn decrypt_login_field(encrypted: &str, enc_key: &str) -> Result<String, crypto::CryptoError> {
+    if encrypted.is_empty() {
+        return Ok(String::new());
+    }
+
+    crypto::decrypt(encrypted, enc_key)
+}
+
+/// Empty values, such as empty custom fields, are stored unencrypted and stay empty.
+fn reencrypt(encrypted: &str, user_id: &str, old_key: &str, new_key: &str) -> Result<String, String> {
+    if encrypted.is_empty() {
+        return Ok(String::new());
+    }
+
+    let plaintext =
+        crypto::decrypt(encrypted, old_key).map_err(|e| format!("Failed to decrypt: {}", e))?;
+    crypto::encrypt(&plaintext, user_id, new_key).map_err(|e| format!("Failed to encrypt: {}", e))
+}
+
+#[tauri::command]
+pub async fn delete_account(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    password: String,
+) -> Result<JsonValue, String> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    if password.trim().is_empty() {
+        return Err("Password cannot be empty".into());
+    }
+
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = ?")
+        .bind(&session.user_id)
+        .fetch_optional(&*db)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or("User not found")?;
+
+    let is_correct_pwd = crypto::verify_password(&password, &user.password_hash)
+        .map_err(|e| format!("Password verification error: {}", e))?;
+
+    if !is_correct_pwd {
+        return Err("Password does not match!".into());
+    }
+
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let deleted_passwords = sqlx::query("DELETE FROM passwords WHERE user_id = ?")
+        .bind(&user.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to delete passwords: {}", e))?
+        .rows_affected();
+
+    // Remaining rows owned by the user are removed through ON DELETE CASCADE.
+    // The audit log has no foreign key and keeps the account's history.
+    sqlx::query("DELETE FROM users WHERE id = ?")
+        .bind(&user.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to delete user: {}", e))?;
+
+    let details = format!("Deleted account and {} passwords", deleted_passwords);
+    audit::record_event(
+        &mut *tx,
+        Some(&user.id),
+        Some(&user.username),
+        audit::ACCOUNT_DELETED,
+        Some(session.source),
+        Some(&details),
+    )
+    .await
+    .map_err(|e| format!("Failed to write audit record: {}", e))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to delete account: {}", e))?;
+
+    session::end_user_sessions(&sessions, &user.id);
+
+    db::truncate_wal(&db)
+        .await
+        .map_err(|e| format!("Account deleted, but failed to clear the write-ahead log: {}", e))?;
+
+    Ok(json!({
+        "message": "Account successfully deleted!"
+    }))
+}
+
+#[tauri::command]
+pub async fn rename_user(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    new_username: String,
+) -> Result<JsonValue, String> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    if new_username.trim().is_empty() {
+        return Err("Username cannot be empty".into());
+    }
+
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = ?")
+        .bind(&session.user_id)
+        .fetch_optional(&*db)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or("User not found")?;
+
+    if user.username == new_username {
+        return Err("New username is the same as the current one".into());
+    }
+
+    let existing_user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE username = ?")
+        .bind(&new_username)
+        .fetch_optional(&*db)
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
-    let existing_user = match existing_user {
-        Some(user) => user,
-        None => return Err("Username does not exist".into()),
-    };
+    if existing_user.is_some() {
+        return Err("Username already exists".into());
+    }
 
-    let is_correct_pwd = crypto::verify_password(&password, &existing_user.password_hash)
-        .map_err(|e| format!("Password verification error: {}", e))?;
+    sqlx::query("UPDATE users SET username = ?, updated_at = ? WHERE id = ?")
+        .bind(&new_username)
+        .bind(Utc::now())
+        .bind(&user.id)
+        .execute(&*db)
+        .await
+        .map_err(|e| format!("Failed to rename user: {}", e))?;
+
+    let details = format!("Renamed from {}", user.username);
+    audit::record_event(
+        &*db,
+        Some(&user.id),
+        Some(&new_username),
+        audit::USERNAME_CHANGED,
+        Some(session.source),
+        Some(&details),
+    )
+    .await
+    .map_err(|e| format!("Failed to write audit record: {}", e))?;
 
-    if !is_correct_pwd {
-        return Err("Password does not match!".into());
-    }
+    Ok(json!({
+        "username": new_username,
+        "message": "Username successfully changed!"
+    }))
+}
 
-    let encrypted_key = crypto::generate_encryption_key(&password)
-        .map_err(|e| format!("Failed to generate encryption key: {}", e))?;
+#[tauri::command]
+pub async fn get_profile(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+) -> Result<JsonValue, String> {
+    let session = session::require_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
 
-    user_state::set_current_user(&user_state, existing_user.id.clone());
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = ?")
+        .bind(&session.user_id)
+        .fetch_optional(&*db)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or("User not found")?;
 
     Ok(json!({
-        "encKey": encrypted_key,
-        "message": "Login successful!"
+        "username": user.username,
+        "display_name": user.display_name,
+        "email_hint": user.email_hint,
+        "password_hint": user.password_hint,
+        "created_at": user.created_at.to_rfc3339(),
+        "updated_at": user.updated_at.to_rfc3339()
     }))
 }
 
 #[tauri::command]
-pub async fn logout_user(user_state: State<'_, UserState>) -> Result<JsonValue, String> {
-    user_state::clear_current_user(&user_state);
+pub async fn update_profile(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    display_name: Option<String>,
+    email_hint: Option<String>,
+    password_hint: Option<String>,
+) -> Result<JsonValue, String> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    // Blank values clear the field
+    let display_name = display_name.filter(|v| !v.trim().is_empty());
+    let email_hint = email_hint.filter(|v| !v.trim().is_empty());
+    let password_hint = password_hint.filter(|v| !v.trim().is_empty());
+
+    sqlx::query(
+        "UPDATE users SET
+        display_name = ?,
+        email_hint = ?,
+        password_hint = ?,
+        updated_at = ?
+        WHERE id = ?",
+    )
+    .bind(&display_name)
+    .bind(&email_hint)
+    .bind(&password_hint)
+    .bind(Utc::now())
+    .bind(&session.user_id)
+    .execute(&*db)
+    .await
+    .map_err(|e| format!("Failed to update profile: {}", e))?;
+
     Ok(json!({
-        "message": "Logout successful!"
+        "message": "Profile successfully updated!"
     }))
 }
 
 #[tauri::command]
 pub async fn new_password(
-    user_state: State<'_, UserState>,
+    sessions: State<'_, SessionRegistry>,
     pool: State<'_, DatabasePool>,
+    session_token: String,
     website: String,
     username: String,
     password: String,
     website_url: Option<String>,
     notes: Option<String>,
-    enc_key: String,
+    folder_id: Option<String>,
+    custom_fields: Option<Vec<CustomField>>,
+    urls: Option<Vec<EntryUrl>>,
 ) -> Result<JsonValue, String> {
-    if user_state::require_authentication(&user_state).is_err() {
-        return Err("Not authenticated".into());
-    }
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
 
     if website.trim().is_empty() {
         return Err("Website cannot be empty".into());
@@ -147,27 +913,54 @@ pub async fn new_password(
         return Err("Password cannot be empty".into());
     }
 
-    let user_id = user_state::get_current_user(&user_state).unwrap();
+    let user_id = session.user_id;
+    let enc_key = session.enc_key;
     let now = Utc::now();
     let password_id = Uuid::new_v4().to_string();
 
+    let custom_fields = custom_fields.unwrap_or_default();
+    custom_fields::validate(&custom_fields)?;
+
+    // A single website_url is still accepted when no URL list is given
+    let urls = entry_urls::normalize(
+        urls.unwrap_or_else(|| entry_urls::with_primary(Vec::new(), website_url)),
+        &[],
+    )?;
+
+    if let Some(folder_id) = &folder_id {
+        folders::require_folder(&*db, &user_id, folder_id).await?;
+    }
+
     let encrypted_username = crypto::encrypt(&username, &user_id, &enc_key)
         .map_err(|e| format!("Failed to encrypt username: {}", e))?;
 
     let encrypted_password = crypto::encrypt(&password, &user_id, &enc_key)
         .map_err(|e| format!("Failed to encrypt password: {}", e))?;
 
-    sqlx::query("INSERT INTO passwords (id, user_id, website, website_url, encrypted_username, encrypted_password, notes, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)")
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    sqlx::query("INSERT INTO passwords (id, user_id, website, encrypted_username, encrypted_password, notes, folder_id, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)")
         .bind(&password_id)
         .bind(&user_id)
         .bind(&website)
-        .bind(&website_url)
         .bind(&encrypted_username)
         .bind(&encrypted_password)
         .bind(&notes)
+        .bind(&folder_id)
         .bind(now)
         .bind(now)
-        .execute(&*pool.0)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to create password: {}", e))?;
+
+    custom_fields::replace_fields(&mut tx, &password_id, &user_id, &enc_key, &custom_fields).await?;
+    entry_urls::replace_urls(&mut tx, &password_id, &urls).await?;
+    crate::revisions::record(&mut tx, &password_id, &user_id, &enc_key).await?;
+
+    tx.commit()
         .await
         .map_err(|e| format!("Failed to create password: {}", e))?;
 
@@ -178,38 +971,53 @@ pub async fn new_password(
 
 #[tauri::command]
 pub async fn get_all_passwords_for_export(
-    user_state: State<'_, UserState>,
+    sessions: State<'_, SessionRegistry>,
     pool: State<'_, DatabasePool>,
-    enc_key: String
-) -> Result<JsonValue, String> {
-    if user_state::require_authentication(&user_state).is_err() {
-        return Err("Not Authenticated".into());
-    }
+    config: State<'_, AppConfig>,
+    session_token: String,
+) -> Result<JsonValue, CommandError> {
+    let session = session::require_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+    session::require_recent_verification(&session, config.reauth_window_minutes)?;
 
-    let user_id = user_state::get_current_user(&user_state).unwrap();
+    let user_id = session.user_id;
+    let enc_key = session.enc_key;
 
     let passwords = sqlx::query_as::<_, PasswordRecord>(
-        "SELECT id, website, website_url, encrypted_username, encrypted_password, notes, updated_at
+        "SELECT id, website, encrypted_username, encrypted_password, notes, item_type, encrypted_data, encrypted_totp, folder_id, favorite, updated_at
         FROM passwords
-        WHERE user_id = ?
+        WHERE user_id = ? AND deleted_at IS NULL
         ORDER BY website ASC"
     ).bind(&user_id)
-    .fetch_all(&*pool.0)
+    .fetch_all(&*db)
     .await
     .map_err(|e| format!("Failed to fetch passwords: {}", e))?;
 
+    let ids: Vec<String> = passwords.iter().map(|password| password.id.clone()).collect();
+    let mut fields = custom_fields::load_fields(&*db, &ids, &enc_key).await?;
+    let mut urls = entry_urls::load_urls(&*db, &ids).await?;
+
     let password_list: Vec<JsonValue> = passwords.into_iter().map(|password| {
-        let username = crypto::decrypt(&password.encrypted_username, &enc_key).unwrap_or_else(|_| "Error decrypting username".to_string());
+        let username = decrypt_login_field(&password.encrypted_username, &enc_key).unwrap_or_else(|_| "Error decrypting username".to_string());
 
-        let decrypted_password = crypto::decrypt(&password.encrypted_password, &enc_key).unwrap_or_else(|_| "Error decrypting username".to_string());
+        let decrypted_password = decrypt_login_field(&password.encrypted_password, &enc_key).unwrap_or_else(|_| "Error decrypting username".to_string());
+
+        let data = password.encrypted_data.as_deref().and_then(|data| ItemData::decrypt(data, &enc_key).ok());
+        let urls = urls.remove(&password.id).unwrap_or_default();
+        let totp = password.encrypted_totp.as_deref().and_then(|totp| Otp::decrypt(totp, &enc_key).ok()).map(|otp| otp.to_uri());
 
         json!({
             "id": password.id,
             "website": password.website,
-            "website_url": password.website_url,
+            "website_url": entry_urls::primary(&urls),
+            "urls": urls,
             "username": username,
             "password": decrypted_password,
             "notes": password.notes,
+            "item_type": password.item_type,
+            "data": data,
+            "totp": totp,
+            "custom_fields": fields.remove(&password.id).unwrap_or_default(),
             "updated_at": password.updated_at.to_rfc3339()
         })
     })
@@ -222,56 +1030,139 @@ pub async fn get_all_passwords_for_export(
 }
 
 
+/// Builds the condition appended after `WHERE user_id = ?` when listing entries,
+/// along with the values to bind straight after the user id.
+fn entry_condition(filter: &EntryFilter) -> (String, Vec<String>) {
+    // Entries in the trash only show up through list_trash
+    let mut condition = String::from(" AND deleted_at IS NULL");
+    let mut binds = Vec::new();
+
+    if let Some(folder_id) = &filter.folder_id {
+        if filter.include_subfolders {
+            condition.push_str(&format!(" AND folder_id IN ({})", folders::SUBTREE));
+        } else {
+            condition.push_str(" AND folder_id = ?");
+        }
+        binds.push(folder_id.clone());
+    }
+
+    // A repeated id would otherwise make an All match require one tag too many
+    let mut tags = filter.tags.clone();
+    tags.sort();
+    tags.dedup();
+
+    if !tags.is_empty() {
+        let placeholders = vec!["?"; tags.len()].join(", ");
+        let having = match filter.tag_match {
+            TagMatch::All => format!(
+                " GROUP BY password_id HAVING COUNT(DISTINCT tag_id) = {}",
+                tags.len()
+            ),
+            TagMatch::Any => String::new(),
+        };
+        condition.push_str(&format!(
+            " AND id IN (SELECT password_id FROM password_tags WHERE tag_id IN ({}){})",
+            placeholders, having
+        ));
+        binds.extend(tags);
+    }
+
+    if let Some(item_type) = filter.item_type {
+        condition.push_str(" AND item_type = ?");
+        binds.push(item_type.as_str().to_string());
+    }
+
+    if filter.favorites_only {
+        condition.push_str(" AND favorite = 1");
+    }
+
+    (condition, binds)
+}
+
+fn entry_order(filter: &EntryFilter) -> &'static str {
+    if filter.favorites_first {
+        "favorite DESC, updated_at DESC"
+    } else {
+        "updated_at DESC"
+    }
+}
+
 #[tauri::command]
 pub async fn get_passwords(
-    user_state: State<'_, UserState>,
+    sessions: State<'_, SessionRegistry>,
     pool: State<'_, DatabasePool>,
+    session_token: String,
     page: i32,
-    enc_key: String,
+    filter: Option<EntryFilter>,
 ) -> Result<JsonValue, String> {
-    if user_state::require_authentication(&user_state).is_err() {
-        return Err("Not authenticated".into());
-    }
+    let session = session::require_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
 
-    let user_id = user_state::get_current_user(&user_state).unwrap();
+    let user_id = session.user_id;
+    let enc_key = session.enc_key;
+    let filter = filter.unwrap_or_default();
+    let (condition, binds) = entry_condition(&filter);
     let page_size = 6;
     let offset = (page - 1) * page_size;
 
-    let total_count =
-        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM passwords WHERE user_id = ?")
-            .bind(&user_id)
-            .fetch_one(&*pool.0)
-            .await
-            .map_err(|e| format!("Failed to count passwords: {}", e))?;
+    let count_sql = format!("SELECT COUNT(*) FROM passwords WHERE user_id = ?{}", condition);
+    let mut count_query = sqlx::query_scalar::<_, i64>(&count_sql).bind(&user_id);
+    for value in &binds {
+        count_query = count_query.bind(value);
+    }
 
-    let passwords = sqlx::query_as::<_, PasswordRecord>(
+    let total_count = count_query
+        .fetch_one(&*db)
+        .await
+        .map_err(|e| format!("Failed to count passwords: {}", e))?;
+
+    let select_sql = format!(
         "
-        SELECT id, website, website_url, encrypted_username, encrypted_password, notes, updated_at 
+        SELECT id, website, encrypted_username, encrypted_password, notes, item_type, encrypted_data, encrypted_totp, folder_id, favorite, updated_at 
         FROM passwords 
-        WHERE user_id = ? 
-        ORDER BY updated_at DESC
+        WHERE user_id = ?{}
+        ORDER BY {}
         LIMIT ? OFFSET ?
     ",
-    )
-    .bind(&user_id)
-    .bind(page_size)
-    .bind(offset)
-    .fetch_all(&*pool.0)
-    .await
-    .map_err(|e| format!("Failed to fetch passwords: {}", e))?;
+        condition,
+        entry_order(&filter)
+    );
+    let mut select_query = sqlx::query_as::<_, PasswordRecord>(&select_sql).bind(&user_id);
+    for value in &binds {
+        select_query = select_query.bind(value);
+    }
+
+    let passwords = select_query
+        .bind(page_size)
+        .bind(offset)
+        .fetch_all(&*db)
+        .await
+        .map_err(|e| format!("Failed to fetch passwords: {}", e))?;
+
+    let tag_counts = tags::tag_counts(&db, &user_id, &condition, &binds).await?;
+    let ids: Vec<String> = passwords.iter().map(|password| password.id.clone()).collect();
+    let mut entry_tags = tags::tags_for_passwords(&db, &ids).await?;
+    let mut urls = entry_urls::load_urls(&*db, &ids).await?;
 
     let total_pages = (total_count as f64 / page_size as f64).ceil() as i32;
 
     let password_list: Vec<JsonValue> = passwords
         .into_iter()
         .map(|password| {
+            let urls = urls.remove(&password.id).unwrap_or_default();
+
             json!({
                 "id": password.id,
                 "website": password.website,
-                "website_url": password.website_url,
-                "username": crypto::decrypt(&password.encrypted_username, &enc_key),
-                "password": crypto::decrypt(&password.encrypted_password, &enc_key),
+                "website_url": entry_urls::primary(&urls),
+                "urls": urls,
+                "username": decrypt_login_field(&password.encrypted_username, &enc_key),
+                "password": decrypt_login_field(&password.encrypted_password, &enc_key),
                 "notes": password.notes,
+                "item_type": password.item_type,
+                "folder_id": password.folder_id,
+                "favorite": password.favorite,
+                "tags": entry_tags.remove(&password.id).unwrap_or_default(),
                 "updated_at": password.updated_at.to_rfc3339()
             })
         })
@@ -279,6 +1170,7 @@ pub async fn get_passwords(
 
     Ok(json!({
         "passwords": password_list,
+        "tag_counts": tag_counts,
         "total": total_count,
         "page": page,
         "total_pages": total_pages
@@ -288,39 +1180,65 @@ pub async fn get_passwords(
 
 #[tauri::command]
 pub async fn get_password_details(
-    user_state: State<'_, UserState>,
+    sessions: State<'_, SessionRegistry>,
     pool: State<'_, DatabasePool>,
+    session_token: String,
     id: String,
-    enc_key: String,
 ) -> Result<JsonValue, String> {
-    if user_state::require_authentication(&user_state).is_err() {
-        return Err("Not authenticated".into());
-    }
+    let session = session::require_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
 
-    let user_id = user_state::get_current_user(&user_state).unwrap();
+    let user_id = session.user_id;
+    let enc_key = session.enc_key;
 
     let password = sqlx::query_as::<_, PasswordRecord>(
         "
-        SELECT id, website, website_url, encrypted_username, encrypted_password, notes, updated_at 
+        SELECT id, website, encrypted_username, encrypted_password, notes, item_type, encrypted_data, encrypted_totp, folder_id, favorite, updated_at 
         FROM passwords 
-        WHERE id = ? AND user_id = ?
+        WHERE id = ? AND user_id = ? AND deleted_at IS NULL
     ",
     )
     .bind(&id)
     .bind(&user_id)
-    .fetch_optional(&*pool.0)
+    .fetch_optional(&*db)
     .await
     .map_err(|e| format!("Failed to fetch password: {}", e))?;
 
     match password {
         Some(pwd) => {
+            let mut entry_tags = tags::tags_for_passwords(&db, std::slice::from_ref(&pwd.id)).await?;
+            let mut fields =
+                custom_fields::load_fields(&*db, std::slice::from_ref(&pwd.id), &enc_key).await?;
+            let urls = entry_urls::load_urls(&*db, std::slice::from_ref(&pwd.id))
+                .await?
+                .remove(&pwd.id)
+                .unwrap_or_default();
+            let data = pwd
+                .encrypted_data
+                .as_deref()
+                .map(|data| ItemData::decrypt(data, &enc_key))
+                .transpose()?;
+            let schedule = crate::rotation::load_schedule(&*db, &pwd.id, &enc_key).await?;
+
             Ok(json!({
                 "id": pwd.id,
                 "website": pwd.website,
-                "website_url": pwd.website_url,
-                "username": crypto::decrypt(&pwd.encrypted_username, &enc_key),
-                "password": crypto::decrypt(&pwd.encrypted_password, &enc_key),
+                "website_url": entry_urls::primary(&urls),
+                "urls": urls,
+                "username": decrypt_login_field(&pwd.encrypted_username, &enc_key),
+                "password": decrypt_login_field(&pwd.encrypted_password, &enc_key),
                 "notes": pwd.notes,
+                "item_type": pwd.item_type,
+                "data": data,
+                "has_totp": pwd.encrypted_totp.is_some(),
+                "expires_on": schedule.expires_on,
+                "rotation_days": schedule.rotation_days,
+                "password_changed_at": schedule.password_changed_at.to_rfc3339(),
+                "rotation_due_on": schedule.due_on(),
+                "folder_id": pwd.folder_id,
+                "favorite": pwd.favorite,
+                "tags": entry_tags.remove(&pwd.id).unwrap_or_default(),
+                "custom_fields": fields.remove(&pwd.id).unwrap_or_default(),
                 "updated_at": pwd.updated_at.to_rfc3339()
             }))
         }
@@ -330,19 +1248,21 @@ pub async fn get_password_details(
 
 #[tauri::command]
 pub async fn update_password(
-    user_state: State<'_, UserState>,
+    sessions: State<'_, SessionRegistry>,
     pool: State<'_, DatabasePool>,
+    config: State<'_, AppConfig>,
+    session_token: String,
     id: String,
     website: String,
     username: String,
     password: String,
     website_url: Option<String>,
     notes: Option<String>,
-    enc_key: String,
+    custom_fields: Option<Vec<CustomField>>,
+    urls: Option<Vec<EntryUrl>>,
 ) -> Result<JsonValue, String> {
-    if user_state::require_authentication(&user_state).is_err() {
-        return Err("Not authenticated".into());
-    }
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
 
     if website.trim().is_empty() {
         return Err("Website cannot be empty".into());
@@ -356,33 +1276,57 @@ pub async fn update_password(
         return Err("Password cannot be empty".into());
     }
 
-    let user_id = user_state::get_current_user(&user_state).unwrap();
+    if let Some(custom_fields) = &custom_fields {
+        custom_fields::validate(custom_fields)?;
+    }
+
+    let user_id = session.user_id;
+    let enc_key = session.enc_key;
     let now = Utc::now();
 
     // Check if password exists and belongs to the user
-    let existing_password = sqlx::query_scalar::<_, i64>(
-        "SELECT COUNT(*) FROM passwords WHERE id = ? AND user_id = ?"
+    let (item_type, previous_password) = sqlx::query_as::<_, (ItemType, String)>(
+        "SELECT item_type, encrypted_password FROM passwords WHERE id = ? AND user_id = ? AND deleted_at IS NULL"
     )
     .bind(&id)
     .bind(&user_id)
-    .fetch_one(&*pool.0)
+    .fetch_optional(&*db)
     .await
-    .map_err(|e| format!("Failed to check password: {}", e))?;
+    .map_err(|e| format!("Failed to check password: {}", e))?
+    .ok_or("Password not found or you don't have permission to edit it")?;
 
-    if existing_password == 0 {
-        return Err("Password not found or you don't have permission to edit it".into());
+    if item_type != ItemType::Login {
+        return Err("This entry is not a login, edit it with update_item".into());
     }
 
+    let existing = entry_urls::load_urls(&*db, std::slice::from_ref(&id))
+        .await?
+        .remove(&id)
+        .unwrap_or_default();
+
+    // Leaving urls out applies website_url to the primary URL and keeps the others
+    let urls = match urls {
+        Some(urls) => urls,
+        None => entry_urls::with_primary(existing.clone(), website_url),
+    };
+    let urls = entry_urls::normalize(urls, &existing)?;
+
     let encrypted_username = crypto::encrypt(&username, &user_id, &enc_key)
         .map_err(|e| format!("Failed to encrypt username: {}", e))?;
 
     let encrypted_password = crypto::encrypt(&password, &user_id, &enc_key)
         .map_err(|e| format!("Failed to encrypt password: {}", e))?;
 
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    crate::revisions::record(&mut tx, &id, &user_id, &enc_key).await?;
+
     sqlx::query(
         "UPDATE passwords SET 
         website = ?, 
-        website_url = ?, 
         encrypted_username = ?, 
         encrypted_password = ?, 
         notes = ?, 
@@ -390,17 +1334,43 @@ pub async fn update_password(
         WHERE id = ? AND user_id = ?"
     )
     .bind(&website)
-    .bind(&website_url)
     .bind(&encrypted_username)
     .bind(&encrypted_password)
     .bind(&notes)
     .bind(now)
     .bind(&id)
     .bind(&user_id)
-    .execute(&*pool.0)
+    .execute(&mut *tx)
     .await
     .map_err(|e| format!("Failed to update password: {}", e))?;
 
+    // Leaving custom_fields out keeps the existing fields
+    if let Some(custom_fields) = &custom_fields {
+        custom_fields::replace_fields(&mut tx, &id, &user_id, &enc_key, custom_fields).await?;
+    }
+
+    entry_urls::replace_urls(&mut tx, &id, &urls).await?;
+
+    // Saving the same password again doesn't add to the history
+    let password_changed = crypto::decrypt(&previous_password, &enc_key)
+        .map_or(true, |previous| previous != password);
+    if password_changed {
+        history::record_previous_password(
+            &mut tx,
+            &id,
+            &previous_password,
+            config.password_history_limit,
+        )
+        .await?;
+        crate::rotation::mark_password_changed(&mut tx, &id).await?;
+    }
+
+    crate::revisions::record(&mut tx, &id, &user_id, &enc_key).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to update password: {}", e))?;
+
     Ok(json!({
         "message": "Password successfully updated!"
     }))
@@ -408,23 +1378,25 @@ pub async fn update_password(
 
 #[tauri::command]
 pub async fn delete_password(
-    user_state: State<'_, UserState>,
+    sessions: State<'_, SessionRegistry>,
     pool: State<'_, DatabasePool>,
+    config: State<'_, AppConfig>,
+    session_token: String,
     id: String,
-) -> Result<JsonValue, String> {
-    if user_state::require_authentication(&user_state).is_err() {
-        return Err("Not authenticated".into());
-    }
+) -> Result<JsonValue, CommandError> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+    session::require_recent_verification(&session, config.reauth_window_minutes)?;
 
-    let user_id = user_state::get_current_user(&user_state).unwrap();
+    let user_id = session.user_id;
 
     // Check if password exists and belongs to the user
     let existing_password = sqlx::query_scalar::<_, i64>(
-        "SELECT COUNT(*) FROM passwords WHERE id = ? AND user_id = ?"
+        "SELECT COUNT(*) FROM passwords WHERE id = ? AND user_id = ? AND deleted_at IS NULL"
     )
     .bind(&id)
     .bind(&user_id)
-    .fetch_one(&*pool.0)
+    .fetch_one(&*db)
     .await
     .map_err(|e| format!("Failed to check password: {}", e))?;
 
@@ -432,35 +1404,64 @@ pub async fn delete_password(
         return Err("Password not found or you don't have permission to delete it".into());
     }
 
-    sqlx::query("DELETE FROM passwords WHERE id = ? AND user_id = ?")
+    // Moved to the trash, where it stays until restored or purged
+    sqlx::query("UPDATE passwords SET deleted_at = ? WHERE id = ? AND user_id = ?")
+        .bind(Utc::now())
         .bind(&id)
         .bind(&user_id)
-        .execute(&*pool.0)
+        .execute(&*db)
         .await
         .map_err(|e| format!("Failed to delete password: {}", e))?;
 
     Ok(json!({
-        "message": "Password successfully deleted!"
+        "message": "Password moved to the trash!"
+    }))
+}
+
+#[tauri::command]
+pub async fn toggle_favorite(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    id: String,
+) -> Result<JsonValue, String> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let favorite = sqlx::query_scalar::<_, bool>(
+        "UPDATE passwords SET favorite = NOT favorite WHERE id = ? AND user_id = ? AND deleted_at IS NULL RETURNING favorite",
+    )
+    .bind(&id)
+    .bind(&session.user_id)
+    .fetch_optional(&*db)
+    .await
+    .map_err(|e| format!("Failed to update favorite: {}", e))?
+    .ok_or("Password not found or you don't have permission to change it")?;
+
+    Ok(json!({
+        "favorite": favorite,
+        "message": if favorite { "Added to favorites!" } else { "Removed from favorites!" }
     }))
 }
 
 #[tauri::command]
 pub async fn search_passwords(
-    user_state: State<'_, UserState>,
+    sessions: State<'_, SessionRegistry>,
     pool: State<'_, DatabasePool>,
+    session_token: String,
     search_term: String,
     page: i32,
-    enc_key: String,
+    filter: Option<EntryFilter>,
 ) -> Result<JsonValue, String> {
-    if user_state::require_authentication(&user_state).is_err() {
-        return Err("Not authenticated".into());
-    }
+    let session = session::require_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
 
-    let user_id = user_state::get_current_user(&user_state).unwrap();
+    let user_id = session.user_id;
+    let enc_key = session.enc_key;
     
     // If search term is empty, return all passwords
     if search_term.trim().is_empty() {
-        return get_passwords(user_state, pool, page, enc_key).await;
+        return get_passwords(sessions, pool, session_token, page, filter).await;
     }
 
     // Use SQL LIKE for case-insensitive search with wildcards
@@ -468,51 +1469,66 @@ pub async fn search_passwords(
     let page_size = 6; // Should match the PAGE_SIZE in JavaScript
     let offset = (page - 1) * page_size;
 
-    // First get the total count of matching items for pagination
-    let total_count = sqlx::query_scalar::<_, i64>(
-        "
-        SELECT COUNT(*) 
-        FROM passwords 
-        WHERE user_id = ? 
+    let filter = filter.unwrap_or_default();
+    let (condition, mut binds) = entry_condition(&filter);
+    let condition = format!(
+        "{}
         AND (
             website LIKE ? 
-            OR website_url LIKE ? 
+            OR id IN (SELECT password_id FROM entry_urls WHERE url LIKE ?)
             OR notes LIKE ?
-        )
+        )",
+        condition
+    );
+    binds.extend([search_pattern.clone(), search_pattern.clone(), search_pattern]);
+
+    // First get the total count of matching items for pagination
+    let count_sql = format!(
         "
-    )
-    .bind(&user_id)
-    .bind(&search_pattern)
-    .bind(&search_pattern)
-    .bind(&search_pattern)
-    .fetch_one(&*pool.0)
-    .await
-    .map_err(|e| format!("Failed to count search results: {}", e))?;
+        SELECT COUNT(*) 
+        FROM passwords 
+        WHERE user_id = ?{}
+        ",
+        condition
+    );
+    let mut count_query = sqlx::query_scalar::<_, i64>(&count_sql).bind(&user_id);
+    for value in &binds {
+        count_query = count_query.bind(value);
+    }
+
+    let total_count = count_query
+        .fetch_one(&*db)
+        .await
+        .map_err(|e| format!("Failed to count search results: {}", e))?;
 
     // Get paginated search results
-    let passwords = sqlx::query_as::<_, PasswordRecord>(
+    let select_sql = format!(
         "
-        SELECT id, website, website_url, encrypted_username, encrypted_password, notes, updated_at 
+        SELECT id, website, encrypted_username, encrypted_password, notes, item_type, encrypted_data, encrypted_totp, folder_id, favorite, updated_at 
         FROM passwords 
-        WHERE user_id = ? 
-        AND (
-            website LIKE ? 
-            OR website_url LIKE ? 
-            OR notes LIKE ?
-        )
-        ORDER BY updated_at DESC
+        WHERE user_id = ?{}
+        ORDER BY {}
         LIMIT ? OFFSET ?
-        "
-    )
-    .bind(&user_id)
-    .bind(&search_pattern)
-    .bind(&search_pattern)
-    .bind(&search_pattern)
-    .bind(page_size)
-    .bind(offset)
-    .fetch_all(&*pool.0)
-    .await
-    .map_err(|e| format!("Failed to search passwords: {}", e))?;
+        ",
+        condition,
+        entry_order(&filter)
+    );
+    let mut select_query = sqlx::query_as::<_, PasswordRecord>(&select_sql).bind(&user_id);
+    for value in &binds {
+        select_query = select_query.bind(value);
+    }
+
+    let passwords = select_query
+        .bind(page_size)
+        .bind(offset)
+        .fetch_all(&*db)
+        .await
+        .map_err(|e| format!("Failed to search passwords: {}", e))?;
+
+    let tag_counts = tags::tag_counts(&db, &user_id, &condition, &binds).await?;
+    let ids: Vec<String> = passwords.iter().map(|password| password.id.clone()).collect();
+    let mut entry_tags = tags::tags_for_passwords(&db, &ids).await?;
+    let mut urls = entry_urls::load_urls(&*db, &ids).await?;
 
     let total_pages = (total_count as f64 / page_size as f64).ceil() as i32;
 
@@ -521,23 +1537,29 @@ pub async fn search_passwords(
     
     for password in passwords {
         // Decrypt the fields
-        let username = crypto::decrypt(&password.encrypted_username, &enc_key)
+        let username = decrypt_login_field(&password.encrypted_username, &enc_key)
             .unwrap_or_else(|_| "Error decoding username".to_string());
         
-        let decrypted_password = crypto::decrypt(&password.encrypted_password, &enc_key)
+        let decrypted_password = decrypt_login_field(&password.encrypted_password, &enc_key)
             .unwrap_or_else(|_| "Error decoding password".to_string());
         
         // Check if username matches search pattern (after decryption)
         let username_match = username.to_lowercase().contains(&search_term.to_lowercase());
+        let urls = urls.remove(&password.id).unwrap_or_default();
         
         // Create the entry
         let entry = json!({
             "id": password.id,
             "website": password.website,
-            "website_url": password.website_url,
+            "website_url": entry_urls::primary(&urls),
+            "urls": urls,
             "username": json!({"Ok": username}),
             "password": json!({"Ok": decrypted_password}),
             "notes": password.notes,
+            "item_type": password.item_type,
+            "folder_id": password.folder_id,
+            "favorite": password.favorite,
+            "tags": entry_tags.remove(&password.id).unwrap_or_default(),
             "updated_at": password.updated_at.to_rfc3339(),
             "match_type": if username_match { "username" } else { "other" }
         });
@@ -545,8 +1567,16 @@ pub async fn search_passwords(
         password_list.push(entry);
     }
     
-    // Sort the list to prioritize username matches
+    // Sort the list to prioritize favorites when requested, then username matches
     password_list.sort_by(|a, b| {
+        if filter.favorites_first {
+            let a_favorite = a["favorite"].as_bool().unwrap_or(false);
+            let b_favorite = b["favorite"].as_bool().unwrap_or(false);
+            if a_favorite != b_favorite {
+                return b_favorite.cmp(&a_favorite);
+            }
+        }
+
         let a_match_type = a["match_type"].as_str().unwrap_or("other");
         let b_match_type = b["match_type"].as_str().unwrap_or("other");
         
@@ -565,6 +1595,7 @@ pub async fn search_passwords(
 
     Ok(json!({
         "passwords": password_list,
+        "tag_counts": tag_counts,
         "total": total_count,
         "page": page,
         "total_pages": total_pages,
@@ -572,24 +1603,87 @@ pub async fn search_passwords(
     }))
 }
 
+/// Lists the logins with a URL matching the page at `url`, favorites first.
 #[tauri::command]
-pub async fn prepare_passwords_for_export(
-    user_state: State<'_, UserState>,
+pub async fn get_passwords_for_url(
+    sessions: State<'_, SessionRegistry>,
     pool: State<'_, DatabasePool>,
-    enc_key: String,
-    selected_ids: Vec<String>,
+    session_token: String,
+    url: String,
 ) -> Result<JsonValue, String> {
-    if user_state::require_authentication(&user_state).is_err() {
-        return Err("Not authenticated".into());
+    let session = session::require_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let user_id = session.user_id;
+    let enc_key = session.enc_key;
+
+    if url.trim().is_empty() {
+        return Err("URL cannot be empty".into());
     }
 
-    let user_id = user_state::get_current_user(&user_state).unwrap();
+    let passwords = sqlx::query_as::<_, PasswordRecord>(
+        "SELECT id, website, encrypted_username, encrypted_password, notes, item_type, encrypted_data, encrypted_totp, folder_id, favorite, updated_at
+        FROM passwords
+        WHERE user_id = ? AND item_type = ? AND deleted_at IS NULL
+        AND id IN (SELECT password_id FROM entry_urls)
+        ORDER BY favorite DESC, website ASC",
+    )
+    .bind(&user_id)
+    .bind(ItemType::Login)
+    .fetch_all(&*db)
+    .await
+    .map_err(|e| format!("Failed to fetch passwords: {}", e))?;
+
+    let ids: Vec<String> = passwords.iter().map(|password| password.id.clone()).collect();
+    let mut urls = entry_urls::load_urls(&*db, &ids).await?;
+    let page = entry_urls::Page::new(&url);
+
+    let password_list: Vec<JsonValue> = passwords
+        .into_iter()
+        .filter_map(|password| {
+            let urls = urls.remove(&password.id).unwrap_or_default();
+            let matched = urls
+                .iter()
+                .map(entry_urls::UrlMatcher::new)
+                .find(|matcher| matcher.matches(&page))?
+                .entry_url();
+
+            Some(json!({
+                "id": password.id,
+                "website": password.website,
+                "username": decrypt_login_field(&password.encrypted_username, &enc_key),
+                "matched_url": matched,
+                "favorite": password.favorite
+            }))
+        })
+        .collect();
+
+    Ok(json!({
+        "passwords": password_list,
+        "count": password_list.len()
+    }))
+}
+
+#[tauri::command]
+pub async fn prepare_passwords_for_export(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    config: State<'_, AppConfig>,
+    session_token: String,
+    selected_ids: Vec<String>,
+) -> Result<JsonValue, CommandError> {
+    let session = session::require_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+    session::require_recent_verification(&session, config.reauth_window_minutes)?;
+
+    let user_id = session.user_id;
+    let enc_key = session.enc_key;
 
     // Prepare the query with an "IN" clause for selected passwords
     let query = format!(
-        "SELECT id, website, website_url, encrypted_username, encrypted_password, notes, updated_at 
+        "SELECT id, website, encrypted_username, encrypted_password, notes, item_type, encrypted_data, encrypted_totp, folder_id, favorite, updated_at 
          FROM passwords 
-         WHERE user_id = ? {}
+         WHERE user_id = ? AND deleted_at IS NULL {}
          ORDER BY website ASC",
         if !selected_ids.is_empty() {
             format!("AND id IN ({})", selected_ids.iter().map(|_| "?").collect::<Vec<_>>().join(","))
@@ -611,25 +1705,45 @@ pub async fn prepare_passwords_for_export(
     
     // Execute the query
     let passwords = query_builder
-        .fetch_all(&*pool.0)
+        .fetch_all(&*db)
         .await
         .map_err(|e| format!("Failed to fetch passwords: {}", e))?;
 
+    let ids: Vec<String> = passwords.iter().map(|password| password.id.clone()).collect();
+    let mut fields = custom_fields::load_fields(&*db, &ids, &enc_key).await?;
+    let mut urls = entry_urls::load_urls(&*db, &ids).await?;
+
     // Decrypt passwords and prepare for export
     let mut password_data = Vec::new();
     for password in passwords {
-        let username = crypto::decrypt(&password.encrypted_username, &enc_key)
+        let username = decrypt_login_field(&password.encrypted_username, &enc_key)
             .unwrap_or_else(|_| "Error decoding username".to_string());
         
-        let decrypted_password = crypto::decrypt(&password.encrypted_password, &enc_key)
+        let decrypted_password = decrypt_login_field(&password.encrypted_password, &enc_key)
             .unwrap_or_else(|_| "Error decoding password".to_string());
         
+        let data = password
+            .encrypted_data
+            .as_deref()
+            .and_then(|data| ItemData::decrypt(data, &enc_key).ok());
+        let totp = password
+            .encrypted_totp
+            .as_deref()
+            .and_then(|totp| Otp::decrypt(totp, &enc_key).ok())
+            .map(|otp| otp.to_uri());
+        let urls = urls.remove(&password.id).unwrap_or_default();
+
         password_data.push(json!({
             "website": password.website,
             "username": username,
             "password": decrypted_password,
-            "website_url": password.website_url,
+            "website_url": entry_urls::primary(&urls),
+            "urls": urls,
             "notes": password.notes,
+            "item_type": password.item_type,
+            "data": data,
+            "totp": totp,
+            "custom_fields": fields.remove(&password.id).unwrap_or_default(),
         }));
     }
 
@@ -642,16 +1756,16 @@ pub async fn prepare_passwords_for_export(
 
 #[tauri::command]
 pub async fn import_passwords_from_data(
-    user_state: State<'_, UserState>,
+    sessions: State<'_, SessionRegistry>,
     pool: State<'_, DatabasePool>,
+    session_token: String,
     passwords_data: Vec<serde_json::Value>,
-    enc_key: String,
 ) -> Result<JsonValue, String> {
-    if user_state::require_authentication(&user_state).is_err() {
-        return Err("Not authenticated".into());
-    }
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
 
-    let user_id = user_state::get_current_user(&user_state).unwrap();
+    let user_id = session.user_id;
+    let enc_key = session.enc_key;
     
     if passwords_data.is_empty() {
         return Err("No passwords data provided".into());
@@ -660,6 +1774,14 @@ pub async fn import_passwords_from_data(
     // Validate passwords
     let mut valid_passwords = Vec::new();
     for pwd in &passwords_data {
+        // Entries other than logins carry their fields in "data" instead
+        if pwd.get("data").is_some_and(|data| !data.is_null()) {
+            if pwd.get("website").and_then(|v| v.as_str()).is_some_and(|website| !website.is_empty()) {
+                valid_passwords.push(pwd);
+            }
+            continue;
+        }
+
         if let (Some(website), Some(username), Some(password)) = (
             pwd.get("website").and_then(|v| v.as_str()),
             pwd.get("username").and_then(|v| v.as_str()),
@@ -682,48 +1804,137 @@ pub async fn import_passwords_from_data(
     
     for pwd in valid_passwords {
         let website = pwd["website"].as_str().unwrap();
-        let username = pwd["username"].as_str().unwrap();
-        let password = pwd["password"].as_str().unwrap();
+        let username = pwd["username"].as_str().unwrap_or_default();
+        let password = pwd["password"].as_str().unwrap_or_default();
         let website_url = pwd.get("website_url").and_then(|v| v.as_str()).map(String::from);
-        let notes = pwd.get("notes").and_then(|v| v.as_str()).map(String::from);
-        
-        // Encrypt sensitive data
-        let encrypted_username = match crypto::encrypt(username, &user_id, &enc_key) {
-            Ok(value) => value,
-            Err(e) => {
+        let urls = match pwd.get("urls").filter(|urls| !urls.is_null()) {
+            Some(value) => serde_json::from_value::<Vec<EntryUrl>>(value.clone())
+                .map_err(|e| e.to_string())
+                .and_then(|urls| entry_urls::normalize(urls, &[])),
+            None => entry_urls::normalize(entry_urls::with_primary(Vec::new(), website_url), &[]),
+        };
+        let urls = match urls {
+            Ok(urls) => urls,
+            Err(_) => {
                 error_count += 1;
-                println!("Failed to encrypt username: {}", e);
+                println!("Invalid URLs for {}", pwd["website"].as_str().unwrap());
                 continue;
             }
         };
+        let notes = pwd.get("notes").and_then(|v| v.as_str()).map(String::from);
+
+        let fields = match pwd.get("custom_fields") {
+            Some(value) => match serde_json::from_value::<Vec<CustomField>>(value.clone()) {
+                Ok(fields) if custom_fields::validate(&fields).is_ok() => fields,
+                _ => {
+                    error_count += 1;
+                    println!("Invalid custom fields for {}", website);
+                    continue;
+                }
+            },
+            None => Vec::new(),
+        };
+
+        let data = match pwd.get("data").filter(|data| !data.is_null()) {
+            Some(value) => match serde_json::from_value::<ItemData>(value.clone())
+                .map_err(|e| e.to_string())
+                .and_then(|mut data| data.normalize().map(|_| data))
+            {
+                Ok(data) => Some(data),
+                Err(_) => {
+                    error_count += 1;
+                    println!("Invalid entry data for {}", website);
+                    continue;
+                }
+            },
+            None => None,
+        };
+        let item_type = data.as_ref().map_or(ItemType::Login, ItemData::item_type);
+
+        let totp = match pwd.get("totp").and_then(|v| v.as_str()).filter(|uri| !uri.is_empty()) {
+            Some(uri) => match Otp::parse(uri).and_then(|otp| otp.encrypt(&user_id, &enc_key)) {
+                Ok(encrypted) => Some(encrypted),
+                Err(_) => {
+                    error_count += 1;
+                    println!("Invalid one-time password for {}", website);
+                    continue;
+                }
+            },
+            None => None,
+        };
         
-        let encrypted_password = match crypto::encrypt(password, &user_id, &enc_key) {
+        // Encrypt sensitive data
+        let encrypted_data = match data.map(|data| data.encrypt(&user_id, &enc_key)).transpose() {
             Ok(value) => value,
             Err(e) => {
                 error_count += 1;
-                println!("Failed to encrypt password: {}", e);
+                println!("{}", e);
                 continue;
             }
         };
+
+        // Other entry types keep their fields in `data` and leave the login columns
+        // empty, whatever the import file carries for them
+        let login = item_type == ItemType::Login;
+
+        let encrypted_username = if !login {
+            String::new()
+        } else {
+            match crypto::encrypt(username, &user_id, &enc_key) {
+                Ok(value) => value,
+                Err(e) => {
+                    error_count += 1;
+                    println!("Failed to encrypt username: {}", e);
+                    continue;
+                }
+            }
+        };
+
+        let encrypted_password = if !login {
+            String::new()
+        } else {
+            match crypto::encrypt(password, &user_id, &enc_key) {
+                Ok(value) => value,
+                Err(e) => {
+                    error_count += 1;
+                    println!("Failed to encrypt password: {}", e);
+                    continue;
+                }
+            }
+        };
         
         // Generate a new password ID
         let password_id = Uuid::new_v4().to_string();
         
-        // Insert into database
-        let result = sqlx::query(
-            "INSERT INTO passwords (id, user_id, website, website_url, encrypted_username, encrypted_password, notes, created_at, updated_at) 
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
-        )
-        .bind(&password_id)
-        .bind(&user_id)
-        .bind(&website)
-        .bind(&website_url)
-        .bind(&encrypted_username)
-        .bind(&encrypted_password)
-        .bind(&notes)
-        .bind(now)
-        .bind(now)
-        .execute(&*pool.0)
+        // Insert the entry and its custom fields together
+        let result: Result<(), String> = async {
+            let mut tx = db.begin().await.map_err(|e| e.to_string())?;
+
+            sqlx::query(
+                "INSERT INTO passwords (id, user_id, website, encrypted_username, encrypted_password, notes, item_type, encrypted_data, encrypted_totp, created_at, updated_at) 
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            )
+            .bind(&password_id)
+            .bind(&user_id)
+            .bind(website)
+            .bind(&encrypted_username)
+            .bind(&encrypted_password)
+            .bind(&notes)
+            .bind(item_type)
+            .bind(&encrypted_data)
+            .bind(&totp)
+            .bind(now)
+            .bind(now)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+
+            custom_fields::replace_fields(&mut tx, &password_id, &user_id, &enc_key, &fields).await?;
+            entry_urls::replace_urls(&mut tx, &password_id, &urls).await?;
+            crate::revisions::record(&mut tx, &password_id, &user_id, &enc_key).await?;
+
+            tx.commit().await.map_err(|e| e.to_string())
+        }
         .await;
         
         match result {
@@ -741,4 +1952,42 @@ pub async fn import_passwords_from_data(
         "error_count": error_count,
         "message": format!("Imported {} passwords with {} errors", success_count, error_count)
     }))
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag_filter(tags: &[&str], tag_match: TagMatch) -> EntryFilter {
+        EntryFilter {
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            tag_match,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn all_tags_match_ignores_repeated_ids() {
+        let (condition, binds) = entry_condition(&tag_filter(&["b", "a", "b"], TagMatch::All));
+
+        assert_eq!(binds, vec!["a", "b"]);
+        assert!(condition.contains("tag_id IN (?, ?)"));
+        assert!(condition.contains("HAVING COUNT(DISTINCT tag_id) = 2"));
+    }
+
+    #[test]
+    fn any_tag_match_has_no_count() {
+        let (condition, binds) = entry_condition(&tag_filter(&["a", "a"], TagMatch::Any));
+
+        assert_eq!(binds, vec!["a"]);
+        assert!(!condition.contains("HAVING"));
+    }
+
+    #[test]
+    fn no_tags_do_not_filter() {
+        let (condition, binds) = entry_condition(&tag_filter(&[], TagMatch::All));
+
+        assert!(binds.is_empty());
+        assert_eq!(condition, " AND deleted_at IS NULL");
+    }
+}
@@ -0,0 +1,266 @@
+use crate::{
+    attachments, config::AppConfig, crypto, data_dir::DataDir, models::AttachmentRecord, session,
+    DatabasePool, SessionRegistry,
+};
+use chrono::Utc;
+use serde_json::{json, Value as JsonValue};
+use std::path::Path;
+use tauri::State;
+use tokio::fs::File;
+use uuid::Uuid;
+
+const BYTES_PER_MB: u64 = 1024 * 1024;
+const MAX_NAME_LENGTH: usize = 255;
+
+/// Attaches the file at `path` to an entry. The file is read and encrypted in
+/// chunks, so it never has to fit in memory as a whole.
+#[tauri::command]
+pub async fn add_attachment(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    config: State<'_, AppConfig>,
+    session_token: String,
+    password_id: String,
+    path: String,
+) -> Result<JsonValue, String> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let user_id = &session.user_id;
+    let enc_key = &session.enc_key;
+
+    let path = Path::new(&path);
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or("Choose a file to attach")?;
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(format!(
+            "File name must be at most {} characters",
+            MAX_NAME_LENGTH
+        ));
+    }
+
+    let used = sqlx::query_scalar::<_, Option<i64>>(
+        "SELECT (SELECT COALESCE(SUM(size), 0) FROM attachments WHERE password_id = p.id)
+         FROM passwords p
+         WHERE p.id = ? AND p.user_id = ? AND p.deleted_at IS NULL",
+    )
+    .bind(&password_id)
+    .bind(user_id)
+    .fetch_optional(&*db)
+    .await
+    .map_err(|e| format!("Failed to check password: {}", e))?
+    .ok_or("Password not found or you don't have permission to edit it")?
+    .unwrap_or(0) as u64;
+
+    let mut file = File::open(path)
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let metadata = file
+        .metadata()
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if !metadata.is_file() {
+        return Err("Choose a file to attach".into());
+    }
+
+    let max_size = config.max_attachment_mb * BYTES_PER_MB;
+    if metadata.len() > max_size {
+        return Err(format!(
+            "Attachments can be at most {} MB",
+            config.max_attachment_mb
+        ));
+    }
+
+    let remaining = (config.max_entry_attachments_mb * BYTES_PER_MB).saturating_sub(used);
+    if metadata.len() > remaining {
+        return Err(format!(
+            "Attachments of an entry can be at most {} MB together",
+            config.max_entry_attachments_mb
+        ));
+    }
+
+    let encrypted_name = crypto::encrypt(&name, user_id, enc_key)
+        .map_err(|e| format!("Failed to encrypt file name: {}", e))?;
+    let attachment_id = Uuid::new_v4().to_string();
+    let now = Utc::now();
+
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    sqlx::query("INSERT INTO attachments (id, password_id, encrypted_name, size, chunk_count, created_at) VALUES (?, ?, ?, 0, 0, ?)")
+        .bind(&attachment_id)
+        .bind(&password_id)
+        .bind(&encrypted_name)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to save attachment: {}", e))?;
+
+    // The limits are enforced again while reading in case the file grows meanwhile
+    let (size, chunk_count) = attachments::store_chunks(
+        &mut tx,
+        &attachment_id,
+        &mut file,
+        max_size.min(remaining),
+        enc_key,
+    )
+    .await?;
+
+    sqlx::query("UPDATE attachments SET size = ?, chunk_count = ? WHERE id = ?")
+        .bind(size as i64)
+        .bind(chunk_count)
+        .bind(&attachment_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to save attachment: {}", e))?;
+
+    sqlx::query("UPDATE passwords SET updated_at = ? WHERE id = ?")
+        .bind(now)
+        .bind(&password_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to save attachment: {}", e))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save attachment: {}", e))?;
+
+    Ok(json!({
+        "id": attachment_id,
+        "name": name,
+        "size": size,
+        "message": "File successfully attached!"
+    }))
+}
+
+#[tauri::command]
+pub async fn list_attachments(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    password_id: String,
+) -> Result<JsonValue, String> {
+    let session = session::require_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let records = sqlx::query_as::<_, AttachmentRecord>(
+        "SELECT a.id, a.password_id, a.encrypted_name, a.size, a.chunk_count, a.created_at
+         FROM attachments a
+         JOIN passwords p ON p.id = a.password_id
+         WHERE a.password_id = ? AND p.user_id = ? AND p.deleted_at IS NULL
+         ORDER BY a.created_at",
+    )
+    .bind(&password_id)
+    .bind(&session.user_id)
+    .fetch_all(&*db)
+    .await
+    .map_err(|e| format!("Failed to fetch attachments: {}", e))?;
+
+    let mut attachments = Vec::with_capacity(records.len());
+    let mut total_size = 0;
+
+    for record in records {
+        let name = crypto::decrypt(&record.encrypted_name, &session.enc_key)
+            .map_err(|e| format!("Failed to decrypt file name: {}", e))?;
+        total_size += record.size;
+
+        attachments.push(json!({
+            "id": record.id,
+            "name": name,
+            "size": record.size,
+            "created_at": record.created_at.to_rfc3339()
+        }));
+    }
+
+    Ok(json!({
+        "attachments": attachments,
+        "total_size": total_size
+    }))
+}
+
+/// Decrypts an attachment into a new file at `destination`, which in portable
+/// mode must be inside the data directory.
+#[tauri::command]
+pub async fn download_attachment(
+    data_dir: State<'_, DataDir>,
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    id: String,
+    destination: String,
+) -> Result<JsonValue, String> {
+    let session = session::require_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    // Check the real directory so `..` or symlinks can't leave the data directory
+    let destination = Path::new(&destination);
+    let file_name = destination
+        .file_name()
+        .ok_or("Destination must name a file")?;
+    let directory = match destination.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let destination = directory
+        .canonicalize()
+        .map_err(|e| format!("Destination folder not found: {}", e))?
+        .join(file_name);
+
+    if !data_dir.allows(&destination) {
+        return Err("Portable mode only allows saving files inside the data directory".into());
+    }
+
+    let chunk_count = sqlx::query_scalar::<_, i64>(
+        "SELECT a.chunk_count
+         FROM attachments a
+         JOIN passwords p ON p.id = a.password_id
+         WHERE a.id = ? AND p.user_id = ? AND p.deleted_at IS NULL",
+    )
+    .bind(&id)
+    .bind(&session.user_id)
+    .fetch_optional(&*db)
+    .await
+    .map_err(|e| format!("Failed to fetch attachment: {}", e))?
+    .ok_or("Attachment not found")?;
+
+    attachments::write_to_file(&db, &id, chunk_count, &destination, &session.enc_key).await?;
+
+    Ok(json!({
+        "message": "Attachment successfully saved!"
+    }))
+}
+
+#[tauri::command]
+pub async fn delete_attachment(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    id: String,
+) -> Result<JsonValue, String> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    // Chunks are removed through ON DELETE CASCADE
+    let deleted = sqlx::query(
+        "DELETE FROM attachments
+         WHERE id = ? AND password_id IN (SELECT id FROM passwords WHERE user_id = ? AND deleted_at IS NULL)",
+    )
+    .bind(&id)
+    .bind(&session.user_id)
+    .execute(&*db)
+    .await
+    .map_err(|e| format!("Failed to delete attachment: {}", e))?
+    .rows_affected();
+
+    if deleted == 0 {
+        return Err("Attachment not found or you don't have permission to delete it".into());
+    }
+
+    Ok(json!({
+        "message": "Attachment successfully deleted!"
+    }))
+}
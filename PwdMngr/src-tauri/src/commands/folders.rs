@@ -0,0 +1,431 @@
+use crate::{
+    config::AppConfig, error::CommandError, models::Folder, revisions, session, DatabasePool,
+    SessionRegistry,
+};
+use chrono::Utc;
+use serde_json::{json, Value as JsonValue};
+use sqlx::{Sqlite, SqliteExecutor};
+use tauri::State;
+use uuid::Uuid;
+
+/// Ids of a folder and every folder nested below it.
+pub(crate) const SUBTREE: &str = "
+    WITH RECURSIVE subtree(id) AS (
+        SELECT ?
+        UNION ALL
+        SELECT f.id FROM folders f JOIN subtree s ON f.parent_id = s.id
+    )
+    SELECT id FROM subtree";
+
+pub(crate) async fn require_folder<'e, E>(
+    executor: E,
+    user_id: &str,
+    folder_id: &str,
+) -> Result<(), String>
+where
+    E: SqliteExecutor<'e>,
+{
+    let exists = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM folders WHERE id = ? AND user_id = ?",
+    )
+    .bind(folder_id)
+    .bind(user_id)
+    .fetch_one(executor)
+    .await
+    .map_err(|e| format!("Failed to check folder: {}", e))?;
+
+    if exists == 0 {
+        return Err("Folder not found or you don't have permission to use it".into());
+    }
+
+    Ok(())
+}
+
+async fn ensure_unique_name<'e, E>(
+    executor: E,
+    user_id: &str,
+    parent_id: Option<&str>,
+    name: &str,
+    except_id: Option<&str>,
+) -> Result<(), String>
+where
+    E: SqliteExecutor<'e>,
+{
+    let taken = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM folders
+         WHERE user_id = ? AND parent_id IS ? AND name = ? COLLATE NOCASE AND id IS NOT ?",
+    )
+    .bind(user_id)
+    .bind(parent_id)
+    .bind(name)
+    .bind(except_id)
+    .fetch_one(executor)
+    .await
+    .map_err(|e| format!("Failed to check folder name: {}", e))?;
+
+    if taken > 0 {
+        return Err("A folder with that name already exists here".into());
+    }
+
+    Ok(())
+}
+
+fn clean_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+
+    if name.is_empty() {
+        return Err("Folder name cannot be empty".into());
+    }
+
+    if name.chars().count() > 100 {
+        return Err("Folder name must be at most 100 characters".into());
+    }
+
+    Ok(name.to_string())
+}
+
+#[tauri::command]
+pub async fn list_folders(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+) -> Result<JsonValue, String> {
+    let session = session::require_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let folders = sqlx::query_as::<_, Folder>(
+        "
+        SELECT f.id, f.parent_id, f.name, f.created_at, f.updated_at,
+            (SELECT COUNT(*) FROM passwords p WHERE p.folder_id = f.id AND p.deleted_at IS NULL) AS password_count
+        FROM folders f
+        WHERE f.user_id = ?
+        ORDER BY f.name COLLATE NOCASE
+    ",
+    )
+    .bind(&session.user_id)
+    .fetch_all(&*db)
+    .await
+    .map_err(|e| format!("Failed to fetch folders: {}", e))?;
+
+    let unfiled = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM passwords WHERE user_id = ? AND folder_id IS NULL AND deleted_at IS NULL",
+    )
+    .bind(&session.user_id)
+    .fetch_one(&*db)
+    .await
+    .map_err(|e| format!("Failed to count passwords: {}", e))?;
+
+    Ok(json!({
+        "folders": folders,
+        "unfiled_count": unfiled
+    }))
+}
+
+#[tauri::command]
+pub async fn create_folder(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    name: String,
+    parent_id: Option<String>,
+) -> Result<JsonValue, String> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let user_id = session.user_id;
+    let name = clean_name(&name)?;
+
+    if let Some(parent_id) = &parent_id {
+        require_folder(&*db, &user_id, parent_id).await?;
+    }
+    ensure_unique_name(&*db, &user_id, parent_id.as_deref(), &name, None).await?;
+
+    let folder_id = Uuid::new_v4().to_string();
+    let now = Utc::now();
+
+    sqlx::query("INSERT INTO folders (id, user_id, parent_id, name, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?)")
+        .bind(&folder_id)
+        .bind(&user_id)
+        .bind(&parent_id)
+        .bind(&name)
+        .bind(now)
+        .bind(now)
+        .execute(&*db)
+        .await
+        .map_err(|e| format!("Failed to create folder: {}", e))?;
+
+    Ok(json!({
+        "id": folder_id,
+        "message": "Folder successfully created!"
+    }))
+}
+
+#[tauri::command]
+pub async fn rename_folder(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    id: String,
+    name: String,
+) -> Result<JsonValue, String> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let user_id = session.user_id;
+    let name = clean_name(&name)?;
+
+    let parent_id = sqlx::query_scalar::<_, Option<String>>(
+        "SELECT parent_id FROM folders WHERE id = ? AND user_id = ?",
+    )
+    .bind(&id)
+    .bind(&user_id)
+    .fetch_optional(&*db)
+    .await
+    .map_err(|e| format!("Failed to fetch folder: {}", e))?
+    .ok_or("Folder not found or you don't have permission to rename it")?;
+
+    ensure_unique_name(&*db, &user_id, parent_id.as_deref(), &name, Some(&id)).await?;
+
+    sqlx::query("UPDATE folders SET name = ?, updated_at = ? WHERE id = ? AND user_id = ?")
+        .bind(&name)
+        .bind(Utc::now())
+        .bind(&id)
+        .bind(&user_id)
+        .execute(&*db)
+        .await
+        .map_err(|e| format!("Failed to rename folder: {}", e))?;
+
+    Ok(json!({
+        "message": "Folder successfully renamed!"
+    }))
+}
+
+#[tauri::command]
+pub async fn move_folder(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    id: String,
+    parent_id: Option<String>,
+) -> Result<JsonValue, String> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let user_id = session.user_id;
+
+    let name = sqlx::query_scalar::<_, String>(
+        "SELECT name FROM folders WHERE id = ? AND user_id = ?",
+    )
+    .bind(&id)
+    .bind(&user_id)
+    .fetch_optional(&*db)
+    .await
+    .map_err(|e| format!("Failed to fetch folder: {}", e))?
+    .ok_or("Folder not found or you don't have permission to move it")?;
+
+    if let Some(parent_id) = &parent_id {
+        require_folder(&*db, &user_id, parent_id).await?;
+
+        // A folder cannot end up inside itself or one of its own subfolders
+        let would_cycle = sqlx::query_scalar::<_, i64>(&format!(
+            "SELECT COUNT(*) FROM ({}) WHERE id = ?",
+            SUBTREE
+        ))
+        .bind(&id)
+        .bind(parent_id)
+        .fetch_one(&*db)
+        .await
+        .map_err(|e| format!("Failed to check folder: {}", e))?;
+
+        if would_cycle > 0 {
+            return Err("A folder cannot be moved into itself or one of its subfolders".into());
+        }
+    }
+
+    ensure_unique_name(&*db, &user_id, parent_id.as_deref(), &name, Some(&id)).await?;
+
+    sqlx::query("UPDATE folders SET parent_id = ?, updated_at = ? WHERE id = ? AND user_id = ?")
+        .bind(&parent_id)
+        .bind(Utc::now())
+        .bind(&id)
+        .bind(&user_id)
+        .execute(&*db)
+        .await
+        .map_err(|e| format!("Failed to move folder: {}", e))?;
+
+    Ok(json!({
+        "message": "Folder successfully moved!"
+    }))
+}
+
+/// `mode` is either "cascade", which deletes every subfolder and entry inside the
+/// folder, or "reparent", which hands them to the folder's own parent.
+#[tauri::command]
+pub async fn delete_folder(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    config: State<'_, AppConfig>,
+    session_token: String,
+    id: String,
+    mode: String,
+) -> Result<JsonValue, CommandError> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    match mode.as_str() {
+        "cascade" => session::require_recent_verification(&session, config.reauth_window_minutes)?,
+        "reparent" => {}
+        _ => return Err("Delete mode must be either cascade or reparent".into()),
+    }
+
+    let user_id = session.user_id;
+
+    let parent_id = sqlx::query_scalar::<_, Option<String>>(
+        "SELECT parent_id FROM folders WHERE id = ? AND user_id = ?",
+    )
+    .bind(&id)
+    .bind(&user_id)
+    .fetch_optional(&*db)
+    .await
+    .map_err(|e| format!("Failed to fetch folder: {}", e))?
+    .ok_or("Folder not found or you don't have permission to delete it")?;
+
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let deleted_passwords = if mode == "cascade" {
+        // Entries go to the trash and lose their folder once it is deleted
+        sqlx::query(&format!(
+            "UPDATE passwords SET deleted_at = ? WHERE user_id = ? AND deleted_at IS NULL AND folder_id IN ({})",
+            SUBTREE
+        ))
+        .bind(Utc::now())
+        .bind(&user_id)
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to delete passwords: {}", e))?
+        .rows_affected()
+    } else {
+        reparent_children(&mut tx, &user_id, &id, parent_id.as_deref()).await?;
+        0
+    };
+
+    // Subfolders left at this point are removed through ON DELETE CASCADE
+    sqlx::query("DELETE FROM folders WHERE id = ? AND user_id = ?")
+        .bind(&id)
+        .bind(&user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to delete folder: {}", e))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to delete folder: {}", e))?;
+
+    Ok(json!({
+        "deleted_passwords": deleted_passwords,
+        "message": "Folder successfully deleted!"
+    }))
+}
+
+async fn reparent_children(
+    tx: &mut sqlx::Transaction<'_, Sqlite>,
+    user_id: &str,
+    folder_id: &str,
+    parent_id: Option<&str>,
+) -> Result<(), String> {
+    let children = sqlx::query_as::<_, (String, String)>(
+        "SELECT id, name FROM folders WHERE parent_id = ? AND user_id = ?",
+    )
+    .bind(folder_id)
+    .bind(user_id)
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(|e| format!("Failed to fetch subfolders: {}", e))?;
+
+    for (child_id, name) in children {
+        ensure_unique_name(&mut **tx, user_id, parent_id, &name, Some(&child_id))
+            .await
+            .map_err(|_| format!("The parent folder already contains a folder named {}", name))?;
+    }
+
+    let now = Utc::now();
+
+    sqlx::query("UPDATE folders SET parent_id = ?, updated_at = ? WHERE parent_id = ? AND user_id = ?")
+        .bind(parent_id)
+        .bind(now)
+        .bind(folder_id)
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to move subfolders: {}", e))?;
+
+    sqlx::query("UPDATE passwords SET folder_id = ? WHERE folder_id = ? AND user_id = ?")
+        .bind(parent_id)
+        .bind(folder_id)
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to move passwords: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_password_folder(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    id: String,
+    folder_id: Option<String>,
+) -> Result<JsonValue, String> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let user_id = session.user_id;
+
+    if let Some(folder_id) = &folder_id {
+        require_folder(&*db, &user_id, folder_id).await?;
+    }
+
+    let existing_password = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM passwords WHERE id = ? AND user_id = ? AND deleted_at IS NULL",
+    )
+    .bind(&id)
+    .bind(&user_id)
+    .fetch_one(&*db)
+    .await
+    .map_err(|e| format!("Failed to check password: {}", e))?;
+
+    if existing_password == 0 {
+        return Err("Password not found or you don't have permission to move it".into());
+    }
+
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    revisions::record(&mut tx, &id, &user_id, &session.enc_key).await?;
+
+    sqlx::query("UPDATE passwords SET folder_id = ? WHERE id = ? AND user_id = ?")
+        .bind(&folder_id)
+        .bind(&id)
+        .bind(&user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to move password: {}", e))?;
+
+    revisions::record(&mut tx, &id, &user_id, &session.enc_key).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to move password: {}", e))?;
+
+    Ok(json!({
+        "message": "Password successfully moved!"
+    }))
+}
@@ -0,0 +1,117 @@
+use crate::{crypto, session, DatabasePool, SessionRegistry};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value as JsonValue};
+use sqlx::{Sqlite, Transaction};
+use tauri::State;
+use uuid::Uuid;
+
+/// Keeps `encrypted_password` as the previous password of an entry and drops the
+/// oldest entries beyond `limit`.
+pub(crate) async fn record_previous_password(
+    tx: &mut Transaction<'_, Sqlite>,
+    password_id: &str,
+    encrypted_password: &str,
+    limit: i64,
+) -> Result<(), String> {
+    if limit > 0 {
+        sqlx::query("INSERT INTO password_history (id, password_id, encrypted_password, replaced_at) VALUES (?, ?, ?, ?)")
+            .bind(Uuid::new_v4().to_string())
+            .bind(password_id)
+            .bind(encrypted_password)
+            .bind(Utc::now())
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| format!("Failed to save password history: {}", e))?;
+    }
+
+    sqlx::query(
+        "DELETE FROM password_history
+         WHERE password_id = ? AND id NOT IN (
+             SELECT id FROM password_history
+             WHERE password_id = ?
+             ORDER BY replaced_at DESC
+             LIMIT ?
+         )",
+    )
+    .bind(password_id)
+    .bind(password_id)
+    .bind(limit.max(0))
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| format!("Failed to prune password history: {}", e))?;
+
+    Ok(())
+}
+
+/// Lists when the previous passwords of an entry were replaced, newest first.
+/// The passwords themselves are revealed one at a time with `reveal_previous_password`.
+#[tauri::command]
+pub async fn get_password_history(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    id: String,
+) -> Result<JsonValue, String> {
+    let session = session::require_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let history = sqlx::query_as::<_, (String, DateTime<Utc>)>(
+        "SELECT h.id, h.replaced_at
+         FROM password_history h
+         JOIN passwords p ON p.id = h.password_id
+         WHERE h.password_id = ? AND p.user_id = ? AND p.deleted_at IS NULL
+         ORDER BY h.replaced_at DESC",
+    )
+    .bind(&id)
+    .bind(&session.user_id)
+    .fetch_all(&*db)
+    .await
+    .map_err(|e| format!("Failed to fetch password history: {}", e))?;
+
+    let history: Vec<JsonValue> = history
+        .into_iter()
+        .map(|(history_id, replaced_at)| {
+            json!({
+                "id": history_id,
+                "replaced_at": replaced_at.to_rfc3339()
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "history": history,
+        "count": history.len()
+    }))
+}
+
+#[tauri::command]
+pub async fn reveal_previous_password(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    history_id: String,
+) -> Result<JsonValue, String> {
+    let session = session::require_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let (encrypted_password, replaced_at) = sqlx::query_as::<_, (String, DateTime<Utc>)>(
+        "SELECT h.encrypted_password, h.replaced_at
+         FROM password_history h
+         JOIN passwords p ON p.id = h.password_id
+         WHERE h.id = ? AND p.user_id = ? AND p.deleted_at IS NULL",
+    )
+    .bind(&history_id)
+    .bind(&session.user_id)
+    .fetch_optional(&*db)
+    .await
+    .map_err(|e| format!("Failed to fetch password history: {}", e))?
+    .ok_or("Previous password not found")?;
+
+    let password = crypto::decrypt(&encrypted_password, &session.enc_key)
+        .map_err(|e| format!("Failed to decrypt password: {}", e))?;
+
+    Ok(json!({
+        "password": password,
+        "replaced_at": replaced_at.to_rfc3339()
+    }))
+}
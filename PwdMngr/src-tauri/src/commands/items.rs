@@ -0,0 +1,400 @@
+use super::folders;
+use crate::{
+    custom_fields, items::identity::Identity, items::ItemData, models::CustomField,
+    models::ItemType, revisions, rotation, session, session::Session, DatabasePool,
+    SessionRegistry,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde_json::{json, Value as JsonValue};
+use sqlx::SqlitePool;
+use tauri::State;
+use uuid::Uuid;
+
+/// Validates and stores a new entry, returning its id.
+pub(crate) async fn insert_item(
+    db: &SqlitePool,
+    session: &Session,
+    title: &str,
+    mut data: ItemData,
+    folder_id: Option<String>,
+    custom_fields: Option<Vec<CustomField>>,
+) -> Result<String, String> {
+    if title.trim().is_empty() {
+        return Err("Title cannot be empty".into());
+    }
+
+    data.normalize()?;
+
+    let custom_fields = custom_fields.unwrap_or_default();
+    custom_fields::validate(&custom_fields)?;
+
+    let user_id = &session.user_id;
+    let enc_key = &session.enc_key;
+    let now = Utc::now();
+    let item_id = Uuid::new_v4().to_string();
+
+    if let Some(folder_id) = &folder_id {
+        folders::require_folder(db, user_id, folder_id).await?;
+    }
+
+    let encrypted_data = data.encrypt(user_id, enc_key)?;
+
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    sqlx::query("INSERT INTO passwords (id, user_id, website, encrypted_username, encrypted_password, item_type, encrypted_data, folder_id, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)")
+        .bind(&item_id)
+        .bind(user_id)
+        .bind(title.trim())
+        // Login columns are required by the table and stay empty
+        .bind("")
+        .bind("")
+        .bind(data.item_type())
+        .bind(&encrypted_data)
+        .bind(&folder_id)
+        .bind(now)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to create entry: {}", e))?;
+
+    custom_fields::replace_fields(&mut tx, &item_id, user_id, enc_key, &custom_fields).await?;
+    revisions::record(&mut tx, &item_id, user_id, enc_key).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to create entry: {}", e))?;
+
+    Ok(item_id)
+}
+
+/// Validates and stores changes to an existing entry, which must keep its type.
+async fn save_item(
+    db: &SqlitePool,
+    session: &Session,
+    id: &str,
+    title: &str,
+    mut data: ItemData,
+    custom_fields: Option<Vec<CustomField>>,
+) -> Result<(), String> {
+    if title.trim().is_empty() {
+        return Err("Title cannot be empty".into());
+    }
+
+    data.normalize()?;
+
+    if let Some(custom_fields) = &custom_fields {
+        custom_fields::validate(custom_fields)?;
+    }
+
+    let user_id = &session.user_id;
+    let enc_key = &session.enc_key;
+
+    let (item_type, previous_data) = sqlx::query_as::<_, (ItemType, Option<String>)>(
+        "SELECT item_type, encrypted_data FROM passwords WHERE id = ? AND user_id = ? AND deleted_at IS NULL",
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| format!("Failed to check entry: {}", e))?
+    .ok_or("Entry not found or you don't have permission to edit it")?;
+
+    if item_type != data.item_type() {
+        return Err("The type of an entry cannot be changed".into());
+    }
+
+    let previous_data = previous_data
+        .as_deref()
+        .map(|previous| ItemData::decrypt(previous, enc_key))
+        .transpose()?;
+    let secret_changed = previous_data.as_ref().and_then(ItemData::secret) != data.secret();
+
+    let encrypted_data = data.encrypt(user_id, enc_key)?;
+
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    revisions::record(&mut tx, id, user_id, enc_key).await?;
+
+    sqlx::query("UPDATE passwords SET website = ?, encrypted_data = ?, updated_at = ? WHERE id = ? AND user_id = ?")
+        .bind(title.trim())
+        .bind(&encrypted_data)
+        .bind(Utc::now())
+        .bind(id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to update entry: {}", e))?;
+
+    if let Some(custom_fields) = &custom_fields {
+        custom_fields::replace_fields(&mut tx, id, user_id, enc_key, custom_fields).await?;
+    }
+
+    if secret_changed {
+        rotation::mark_password_changed(&mut tx, id).await?;
+    }
+
+    revisions::record(&mut tx, id, user_id, enc_key).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to update entry: {}", e))?;
+
+    Ok(())
+}
+
+/// Creates an entry of any type other than a login, which still goes through `new_password`.
+#[tauri::command]
+pub async fn new_item(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    title: String,
+    data: ItemData,
+    folder_id: Option<String>,
+    custom_fields: Option<Vec<CustomField>>,
+) -> Result<JsonValue, String> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let item_id = insert_item(&db, &session, &title, data, folder_id, custom_fields).await?;
+
+    Ok(json!({
+        "id": item_id,
+        "message": "Entry successfully saved!"
+    }))
+}
+
+#[tauri::command]
+pub async fn update_item(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    id: String,
+    title: String,
+    data: ItemData,
+    custom_fields: Option<Vec<CustomField>>,
+) -> Result<JsonValue, String> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    save_item(&db, &session, &id, &title, data, custom_fields).await?;
+
+    Ok(json!({
+        "message": "Entry successfully updated!"
+    }))
+}
+
+#[tauri::command]
+pub async fn new_identity(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    title: String,
+    identity: Identity,
+    folder_id: Option<String>,
+    custom_fields: Option<Vec<CustomField>>,
+) -> Result<JsonValue, String> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let data = ItemData::Identity(identity);
+    let item_id = insert_item(&db, &session, &title, data, folder_id, custom_fields).await?;
+
+    Ok(json!({
+        "id": item_id,
+        "message": "Identity successfully saved!"
+    }))
+}
+
+#[tauri::command]
+pub async fn update_identity(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    id: String,
+    title: String,
+    identity: Identity,
+    custom_fields: Option<Vec<CustomField>>,
+) -> Result<JsonValue, String> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let data = ItemData::Identity(identity);
+    save_item(&db, &session, &id, &title, data, custom_fields).await?;
+
+    Ok(json!({
+        "message": "Identity successfully updated!"
+    }))
+}
+
+#[tauri::command]
+pub async fn get_identity(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    id: String,
+) -> Result<JsonValue, String> {
+    let session = session::require_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let (title, encrypted_data, folder_id, favorite, updated_at) =
+        sqlx::query_as::<_, (String, String, Option<String>, bool, DateTime<Utc>)>(
+            "SELECT website, encrypted_data, folder_id, favorite, updated_at
+         FROM passwords
+         WHERE id = ? AND user_id = ? AND item_type = ? AND deleted_at IS NULL",
+        )
+        .bind(&id)
+        .bind(&session.user_id)
+        .bind(ItemType::Identity)
+        .fetch_optional(&*db)
+        .await
+        .map_err(|e| format!("Failed to fetch identity: {}", e))?
+        .ok_or("Identity not found")?;
+
+    let ItemData::Identity(identity) = ItemData::decrypt(&encrypted_data, &session.enc_key)? else {
+        return Err("Identity not found".into());
+    };
+
+    let mut fields =
+        custom_fields::load_fields(&*db, std::slice::from_ref(&id), &session.enc_key).await?;
+
+    Ok(json!({
+        "id": id,
+        "title": title,
+        "identity": identity,
+        "custom_fields": fields.remove(&id).unwrap_or_default(),
+        "folder_id": folder_id,
+        "favorite": favorite,
+        "updated_at": updated_at.to_rfc3339()
+    }))
+}
+
+/// Lists cards that expire within `within_days` (30 by default), including cards
+/// that have already expired, soonest first. Card numbers are reduced to their last
+/// four digits.
+#[tauri::command]
+pub async fn get_expiring_cards(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    within_days: Option<i64>,
+) -> Result<JsonValue, String> {
+    let session = session::require_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let today = Utc::now().date_naive();
+    let cutoff = today + Duration::days(within_days.unwrap_or(30).max(0));
+
+    // Expiry is encrypted along with the rest of the card, so filtering happens here
+    let cards = sqlx::query_as::<_, (String, String, String)>(
+        "SELECT id, website, encrypted_data FROM passwords WHERE user_id = ? AND item_type = ? AND deleted_at IS NULL",
+    )
+    .bind(&session.user_id)
+    .bind(ItemType::Card)
+    .fetch_all(&*db)
+    .await
+    .map_err(|e| format!("Failed to fetch cards: {}", e))?;
+
+    let mut expiring = Vec::new();
+
+    for (id, title, encrypted_data) in cards {
+        let ItemData::Card(card) = ItemData::decrypt(&encrypted_data, &session.enc_key)? else {
+            continue;
+        };
+        let Some(expires_on) = card.expires_on() else {
+            continue;
+        };
+
+        if expires_on <= cutoff {
+            expiring.push((
+                expires_on,
+                json!({
+                    "id": id,
+                    "title": title,
+                    "brand": card.brand,
+                    "last_four": card.last_four(),
+                    "expiry_month": card.expiry_month,
+                    "expiry_year": card.expiry_year,
+                    "expires_on": expires_on.to_string(),
+                    "expired": expires_on < today
+                }),
+            ));
+        }
+    }
+
+    expiring.sort_by_key(|(expires_on, _)| *expires_on);
+    let cards: Vec<JsonValue> = expiring.into_iter().map(|(_, card)| card).collect();
+
+    Ok(json!({
+        "cards": cards,
+        "count": cards.len()
+    }))
+}
+
+/// Lists API credentials that expire within `within_days` (30 by default),
+/// including ones that have already expired, soonest first. Credentials without an
+/// expiry date are left out and secrets are not returned.
+#[tauri::command]
+pub async fn get_expiring_api_credentials(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    within_days: Option<i64>,
+) -> Result<JsonValue, String> {
+    let session = session::require_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let today = Utc::now().date_naive();
+    let cutoff = today + Duration::days(within_days.unwrap_or(30).max(0));
+
+    let credentials = sqlx::query_as::<_, (String, String, String)>(
+        "SELECT id, website, encrypted_data FROM passwords WHERE user_id = ? AND item_type = ? AND deleted_at IS NULL",
+    )
+    .bind(&session.user_id)
+    .bind(ItemType::ApiCredential)
+    .fetch_all(&*db)
+    .await
+    .map_err(|e| format!("Failed to fetch API credentials: {}", e))?;
+
+    let mut expiring = Vec::new();
+
+    for (id, title, encrypted_data) in credentials {
+        let ItemData::ApiCredential(credential) =
+            ItemData::decrypt(&encrypted_data, &session.enc_key)?
+        else {
+            continue;
+        };
+        let Some(expires_on) = credential.expires_on else {
+            continue;
+        };
+
+        if expires_on <= cutoff {
+            expiring.push((
+                expires_on,
+                json!({
+                    "id": id,
+                    "title": title,
+                    "key_id": credential.key_id,
+                    "issuer": credential.issuer,
+                    "scopes": credential.scopes,
+                    "expires_on": expires_on.to_string(),
+                    "expired": expires_on < today
+                }),
+            ));
+        }
+    }
+
+    expiring.sort_by_key(|(expires_on, _)| *expires_on);
+    let credentials: Vec<JsonValue> = expiring.into_iter().map(|(_, c)| c).collect();
+
+    Ok(json!({
+        "credentials": credentials,
+        "count": credentials.len()
+    }))
+}
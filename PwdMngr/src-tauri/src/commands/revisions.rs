@@ -0,0 +1,274 @@
+use super::{folders, history, tags};
+use crate::{
+    config::AppConfig,
+    crypto, custom_fields, entry_urls,
+    items::ItemData,
+    models::ItemType,
+    otp::OtpKind,
+    revisions::{self, Snapshot},
+    rotation, session, DatabasePool, SessionRegistry,
+};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value as JsonValue};
+use sqlx::SqlitePool;
+use tauri::State;
+
+async fn fetch_revision(
+    db: &SqlitePool,
+    user_id: &str,
+    password_id: &str,
+    revision_id: &str,
+    enc_key: &str,
+) -> Result<(i64, DateTime<Utc>, Snapshot), String> {
+    let (number, created_at, encrypted_snapshot) =
+        sqlx::query_as::<_, (i64, DateTime<Utc>, String)>(
+            "SELECT r.number, r.created_at, r.encrypted_snapshot
+             FROM entry_revisions r
+             JOIN passwords p ON p.id = r.password_id
+             WHERE r.id = ? AND r.password_id = ? AND p.user_id = ? AND p.deleted_at IS NULL",
+        )
+        .bind(revision_id)
+        .bind(password_id)
+        .bind(user_id)
+        .fetch_optional(db)
+        .await
+        .map_err(|e| format!("Failed to fetch revision: {}", e))?
+        .ok_or("Revision not found")?;
+
+    Ok((
+        number,
+        created_at,
+        Snapshot::decrypt(&encrypted_snapshot, enc_key)?,
+    ))
+}
+
+/// Lists the revisions of an entry, newest first, with the fields each one changed.
+#[tauri::command]
+pub async fn list_revisions(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    id: String,
+) -> Result<JsonValue, String> {
+    let session = session::require_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let revisions = sqlx::query_as::<_, (String, i64, String, DateTime<Utc>)>(
+        "SELECT r.id, r.number, r.changed_fields, r.created_at
+         FROM entry_revisions r
+         JOIN passwords p ON p.id = r.password_id
+         WHERE r.password_id = ? AND p.user_id = ? AND p.deleted_at IS NULL
+         ORDER BY r.number DESC",
+    )
+    .bind(&id)
+    .bind(&session.user_id)
+    .fetch_all(&*db)
+    .await
+    .map_err(|e| format!("Failed to fetch revisions: {}", e))?;
+
+    let revisions: Vec<JsonValue> = revisions
+        .into_iter()
+        .map(|(revision_id, number, changed_fields, created_at)| {
+            let changed_fields: Vec<String> =
+                serde_json::from_str(&changed_fields).unwrap_or_default();
+
+            json!({
+                "id": revision_id,
+                "number": number,
+                "changed_fields": changed_fields,
+                "created_at": created_at.to_rfc3339()
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "revisions": revisions,
+        "count": revisions.len()
+    }))
+}
+
+/// Compares two revisions of an entry field by field.
+#[tauri::command]
+pub async fn diff_revisions(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    id: String,
+    from_revision_id: String,
+    to_revision_id: String,
+) -> Result<JsonValue, String> {
+    let session = session::require_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let (from_number, from_created_at, from) = fetch_revision(
+        &db,
+        &session.user_id,
+        &id,
+        &from_revision_id,
+        &session.enc_key,
+    )
+    .await?;
+    let (to_number, to_created_at, to) = fetch_revision(
+        &db,
+        &session.user_id,
+        &id,
+        &to_revision_id,
+        &session.enc_key,
+    )
+    .await?;
+
+    let changes: Vec<JsonValue> = from
+        .fields()
+        .into_iter()
+        .zip(to.fields())
+        .filter(|((_, before), (_, after))| before != after)
+        .map(|((field, before), (_, after))| {
+            json!({
+                "field": field,
+                "before": before,
+                "after": after
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "from": { "id": from_revision_id, "number": from_number, "created_at": from_created_at.to_rfc3339() },
+        "to": { "id": to_revision_id, "number": to_number, "created_at": to_created_at.to_rfc3339() },
+        "changes": changes
+    }))
+}
+
+/// Puts an entry back the way it was in a revision. The restored state is saved as
+/// a new revision, so the versions in between stay available.
+#[tauri::command]
+pub async fn restore_revision(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    config: State<'_, AppConfig>,
+    session_token: String,
+    id: String,
+    revision_id: String,
+) -> Result<JsonValue, String> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let user_id = &session.user_id;
+    let enc_key = &session.enc_key;
+
+    let in_trash = sqlx::query_scalar::<_, bool>(
+        "SELECT deleted_at IS NOT NULL FROM passwords WHERE id = ? AND user_id = ?",
+    )
+    .bind(&id)
+    .bind(user_id)
+    .fetch_optional(&*db)
+    .await
+    .map_err(|e| format!("Failed to check entry: {}", e))?
+    .ok_or("Entry not found or you don't have permission to edit it")?;
+
+    if in_trash {
+        return Err("Restore the entry from the trash first".into());
+    }
+
+    let (number, _, snapshot) = fetch_revision(&db, user_id, &id, &revision_id, enc_key).await?;
+
+    // The folder may have been deleted since
+    let folder_id = match snapshot.folder_id.clone() {
+        Some(folder_id)
+            if folders::require_folder(&*db, user_id, &folder_id)
+                .await
+                .is_ok() =>
+        {
+            Some(folder_id)
+        }
+        _ => None,
+    };
+
+    let login = snapshot.item_type == ItemType::Login;
+    let (encrypted_username, encrypted_password) = if login {
+        (
+            crypto::encrypt(&snapshot.username, user_id, enc_key)
+                .map_err(|e| format!("Failed to encrypt username: {}", e))?,
+            crypto::encrypt(&snapshot.password, user_id, enc_key)
+                .map_err(|e| format!("Failed to encrypt password: {}", e))?,
+        )
+    } else {
+        (String::new(), String::new())
+    };
+    let encrypted_data = snapshot
+        .data
+        .as_ref()
+        .map(|data| data.encrypt(user_id, enc_key))
+        .transpose()?;
+
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    revisions::record(&mut tx, &id, user_id, enc_key).await?;
+    let current = revisions::load_snapshot(&mut tx, &id, enc_key).await?;
+
+    // An HOTP counter never goes back, or codes that were already used would work again
+    let encrypted_totp = snapshot
+        .totp
+        .clone()
+        .map(|mut otp| {
+            if let Some(current) = current
+                .totp
+                .as_ref()
+                .filter(|current| current.kind == OtpKind::Hotp && current.secret == otp.secret)
+            {
+                otp.counter = otp.counter.max(current.counter);
+            }
+            otp.encrypt(user_id, enc_key)
+        })
+        .transpose()?;
+
+    sqlx::query("UPDATE passwords SET website = ?, encrypted_username = ?, encrypted_password = ?, notes = ?, folder_id = ?, encrypted_data = ?, encrypted_totp = ?, expires_on = ?, rotation_days = ?, updated_at = ? WHERE id = ?")
+        .bind(&snapshot.website)
+        .bind(&encrypted_username)
+        .bind(&encrypted_password)
+        .bind(&snapshot.notes)
+        .bind(&folder_id)
+        .bind(&encrypted_data)
+        .bind(&encrypted_totp)
+        .bind(snapshot.expires_on)
+        .bind(snapshot.rotation_days)
+        .bind(Utc::now())
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to restore revision: {}", e))?;
+
+    custom_fields::replace_fields(&mut tx, &id, user_id, enc_key, &snapshot.custom_fields).await?;
+    entry_urls::replace_urls(&mut tx, &id, &snapshot.urls).await?;
+    tags::replace_tags(&mut tx, &id, user_id, &snapshot.tags).await?;
+
+    if login && current.password != snapshot.password {
+        let previous_password = crypto::encrypt(&current.password, user_id, enc_key)
+            .map_err(|e| format!("Failed to encrypt password: {}", e))?;
+        history::record_previous_password(
+            &mut tx,
+            &id,
+            &previous_password,
+            config.password_history_limit,
+        )
+        .await?;
+        rotation::mark_password_changed(&mut tx, &id).await?;
+    } else if !login
+        && current.data.as_ref().and_then(ItemData::secret)
+            != snapshot.data.as_ref().and_then(ItemData::secret)
+    {
+        rotation::mark_password_changed(&mut tx, &id).await?;
+    }
+
+    revisions::record(&mut tx, &id, user_id, enc_key).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to restore revision: {}", e))?;
+
+    Ok(json!({
+        "message": format!("Restored revision {}", number)
+    }))
+}
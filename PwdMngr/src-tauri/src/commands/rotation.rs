@@ -0,0 +1,98 @@
+use crate::{
+    config::AppConfig, models::ItemType, revisions, rotation, session, DatabasePool,
+    SessionRegistry,
+};
+use chrono::NaiveDate;
+use serde_json::{json, Value as JsonValue};
+use tauri::State;
+
+/// Sets when an entry expires and how often its password must be rotated.
+/// Leaving both out clears the schedule. API credentials keep the expiry date
+/// stored in the credential.
+#[tauri::command]
+pub async fn set_entry_rotation(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    id: String,
+    expires_on: Option<NaiveDate>,
+    rotation_days: Option<i64>,
+) -> Result<JsonValue, String> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    rotation::validate_rotation_days(rotation_days)?;
+
+    let user_id = &session.user_id;
+
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let item_type = sqlx::query_scalar::<_, ItemType>(
+        "SELECT item_type FROM passwords WHERE id = ? AND user_id = ? AND deleted_at IS NULL",
+    )
+    .bind(&id)
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to fetch password: {}", e))?
+    .ok_or("Password not found")?;
+
+    if item_type == ItemType::ApiCredential && expires_on.is_some() {
+        return Err("The expiry date of an API credential is set on the credential".into());
+    }
+
+    revisions::record(&mut tx, &id, user_id, &session.enc_key).await?;
+
+    sqlx::query("UPDATE passwords SET expires_on = ?, rotation_days = ? WHERE id = ?")
+        .bind(expires_on)
+        .bind(rotation_days)
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to save rotation schedule: {}", e))?;
+
+    revisions::record(&mut tx, &id, user_id, &session.enc_key).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save rotation schedule: {}", e))?;
+
+    let schedule = rotation::load_schedule(&*db, &id, &session.enc_key).await?;
+
+    Ok(json!({
+        "expires_on": schedule.expires_on,
+        "rotation_days": schedule.rotation_days,
+        "password_changed_at": schedule.password_changed_at.to_rfc3339(),
+        "due_on": schedule.due_on()
+    }))
+}
+
+/// Lists entries past their expiry or rotation date and those due within
+/// `within_days`, which defaults to the configured warning period.
+#[tauri::command]
+pub async fn get_rotation_due(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    config: State<'_, AppConfig>,
+    session_token: String,
+    within_days: Option<i64>,
+) -> Result<JsonValue, String> {
+    let session = session::require_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let within_days = within_days.unwrap_or(config.rotation_warning_days);
+    let (overdue, due_soon): (Vec<_>, Vec<_>) =
+        rotation::due_entries(&*db, &session.user_id, &session.enc_key, within_days)
+            .await?
+            .into_iter()
+            .partition(|entry| entry.overdue);
+
+    Ok(json!({
+        "count": overdue.len() + due_soon.len(),
+        "overdue": overdue,
+        "due_soon": due_soon
+    }))
+}
@@ -0,0 +1,114 @@
+use super::items::insert_item;
+use crate::{
+    items::ssh_key::SshKey, items::ItemData, models::ItemType, session, DatabasePool,
+    SessionRegistry,
+};
+use serde_json::{json, Value as JsonValue};
+use tauri::State;
+
+#[tauri::command]
+pub async fn import_ssh_key(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    title: String,
+    private_key: String,
+    passphrase: Option<String>,
+    comment: Option<String>,
+    folder_id: Option<String>,
+) -> Result<JsonValue, String> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let key = SshKey::import(
+        &private_key,
+        passphrase.as_deref(),
+        comment.as_deref().unwrap_or_default(),
+    )?;
+    let public_key = key.public_key.clone();
+    let fingerprint = key.fingerprint.clone();
+
+    let item_id = insert_item(
+        &db,
+        &session,
+        &title,
+        ItemData::SshKey(key),
+        folder_id,
+        None,
+    )
+    .await?;
+
+    Ok(json!({
+        "id": item_id,
+        "public_key": public_key,
+        "fingerprint": fingerprint,
+        "message": "SSH key successfully imported!"
+    }))
+}
+
+/// Generates a new Ed25519 key pair and stores it as an entry.
+#[tauri::command]
+pub async fn generate_ssh_key(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    title: String,
+    comment: Option<String>,
+    folder_id: Option<String>,
+) -> Result<JsonValue, String> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let key = SshKey::generate_ed25519(comment.as_deref().unwrap_or_default())?;
+    let public_key = key.public_key.clone();
+    let fingerprint = key.fingerprint.clone();
+
+    let item_id = insert_item(
+        &db,
+        &session,
+        &title,
+        ItemData::SshKey(key),
+        folder_id,
+        None,
+    )
+    .await?;
+
+    Ok(json!({
+        "id": item_id,
+        "public_key": public_key,
+        "fingerprint": fingerprint,
+        "message": "SSH key successfully generated!"
+    }))
+}
+
+/// Returns the public key as a single `authorized_keys` line.
+#[tauri::command]
+pub async fn export_ssh_public_key(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    id: String,
+) -> Result<JsonValue, String> {
+    let session = session::require_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let encrypted_data = sqlx::query_scalar::<_, String>(
+        "SELECT encrypted_data FROM passwords WHERE id = ? AND user_id = ? AND item_type = ? AND deleted_at IS NULL",
+    )
+    .bind(&id)
+    .bind(&session.user_id)
+    .bind(ItemType::SshKey)
+    .fetch_optional(&*db)
+    .await
+    .map_err(|e| format!("Failed to fetch SSH key: {}", e))?
+    .ok_or("SSH key not found")?;
+
+    let ItemData::SshKey(key) = ItemData::decrypt(&encrypted_data, &session.enc_key)? else {
+        return Err("SSH key not found".into());
+    };
+
+    Ok(json!({
+        "authorized_keys": format!("{}\n", key.public_key),
+        "fingerprint": key.fingerprint
+    }))
+}
@@ -0,0 +1,521 @@
+use crate::{models::Tag, revisions, session, DatabasePool, SessionRegistry};
+use chrono::Utc;
+use serde_json::{json, Value as JsonValue};
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use std::collections::HashMap;
+use tauri::State;
+use uuid::Uuid;
+
+fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(", ")
+}
+
+fn clean_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+
+    if name.is_empty() {
+        return Err("Tag name cannot be empty".into());
+    }
+
+    if name.chars().count() > 50 {
+        return Err("Tag name must be at most 50 characters".into());
+    }
+
+    Ok(name.to_string())
+}
+
+/// Links an entry to tags by name, creating any tag that does not exist yet.
+async fn add_tags(
+    tx: &mut Transaction<'_, Sqlite>,
+    password_id: &str,
+    user_id: &str,
+    names: &[String],
+) -> Result<(), String> {
+    let now = Utc::now();
+
+    for name in names {
+        sqlx::query("INSERT OR IGNORE INTO tags (id, user_id, name, created_at, updated_at) VALUES (?, ?, ?, ?, ?)")
+            .bind(Uuid::new_v4().to_string())
+            .bind(user_id)
+            .bind(name)
+            .bind(now)
+            .bind(now)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| format!("Failed to create tag: {}", e))?;
+
+        sqlx::query(
+            "INSERT OR IGNORE INTO password_tags (password_id, tag_id)
+             SELECT ?, id FROM tags WHERE user_id = ? AND name = ?",
+        )
+        .bind(password_id)
+        .bind(user_id)
+        .bind(name)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to tag password: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Gives an entry exactly the named tags, recreating tags deleted since.
+pub(crate) async fn replace_tags(
+    tx: &mut Transaction<'_, Sqlite>,
+    password_id: &str,
+    user_id: &str,
+    names: &[String],
+) -> Result<(), String> {
+    sqlx::query("DELETE FROM password_tags WHERE password_id = ?")
+        .bind(password_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to untag password: {}", e))?;
+
+    add_tags(tx, password_id, user_id, names).await
+}
+
+/// Entries of a user that carry any of the tags, whose revisions change along with the tags.
+async fn tagged_entries(
+    tx: &mut Transaction<'_, Sqlite>,
+    user_id: &str,
+    tag_ids: &[String],
+) -> Result<Vec<String>, String> {
+    let sql = format!(
+        "SELECT DISTINCT pt.password_id
+         FROM password_tags pt
+         JOIN passwords p ON p.id = pt.password_id
+         WHERE p.user_id = ? AND pt.tag_id IN ({})",
+        placeholders(tag_ids.len())
+    );
+
+    let mut query = sqlx::query_scalar::<_, String>(&sql).bind(user_id);
+    for id in tag_ids {
+        query = query.bind(id);
+    }
+
+    query
+        .fetch_all(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to fetch tagged entries: {}", e))
+}
+
+async fn record_revisions(
+    tx: &mut Transaction<'_, Sqlite>,
+    password_ids: &[String],
+    user_id: &str,
+    enc_key: &str,
+) -> Result<(), String> {
+    for password_id in password_ids {
+        revisions::record(tx, password_id, user_id, enc_key).await?;
+    }
+
+    Ok(())
+}
+
+/// Counts how many of the entries matched by `condition` carry each tag.
+/// `condition` and `binds` follow the same rules as `entry_condition` in the parent module.
+pub(crate) async fn tag_counts(
+    db: &SqlitePool,
+    user_id: &str,
+    condition: &str,
+    binds: &[String],
+) -> Result<Vec<Tag>, String> {
+    let sql = format!(
+        "
+        SELECT t.id, t.name, COUNT(*) AS password_count
+        FROM tags t
+        JOIN password_tags pt ON pt.tag_id = t.id
+        WHERE t.user_id = ?
+        AND pt.password_id IN (SELECT id FROM passwords WHERE user_id = ?{})
+        GROUP BY t.id, t.name
+        ORDER BY t.name
+    ",
+        condition
+    );
+
+    let mut query = sqlx::query_as::<_, Tag>(&sql).bind(user_id).bind(user_id);
+    for value in binds {
+        query = query.bind(value);
+    }
+
+    query
+        .fetch_all(db)
+        .await
+        .map_err(|e| format!("Failed to count tags: {}", e))
+}
+
+/// Returns the tags of each listed entry, keyed by entry id.
+pub(crate) async fn tags_for_passwords(
+    db: &SqlitePool,
+    password_ids: &[String],
+) -> Result<HashMap<String, Vec<JsonValue>>, String> {
+    let mut tags: HashMap<String, Vec<JsonValue>> = HashMap::new();
+
+    if password_ids.is_empty() {
+        return Ok(tags);
+    }
+
+    let sql = format!(
+        "
+        SELECT pt.password_id, t.id, t.name
+        FROM password_tags pt
+        JOIN tags t ON t.id = pt.tag_id
+        WHERE pt.password_id IN ({})
+        ORDER BY t.name
+    ",
+        placeholders(password_ids.len())
+    );
+
+    let mut query = sqlx::query_as::<_, (String, String, String)>(&sql);
+    for id in password_ids {
+        query = query.bind(id);
+    }
+
+    let rows = query
+        .fetch_all(db)
+        .await
+        .map_err(|e| format!("Failed to fetch tags: {}", e))?;
+
+    for (password_id, tag_id, name) in rows {
+        tags.entry(password_id)
+            .or_default()
+            .push(json!({ "id": tag_id, "name": name }));
+    }
+
+    Ok(tags)
+}
+
+#[tauri::command]
+pub async fn list_tags(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+) -> Result<JsonValue, String> {
+    let session = session::require_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let tags = sqlx::query_as::<_, Tag>(
+        "
+        SELECT t.id, t.name, COUNT(p.id) AS password_count
+        FROM tags t
+        LEFT JOIN password_tags pt ON pt.tag_id = t.id
+        LEFT JOIN passwords p ON p.id = pt.password_id AND p.deleted_at IS NULL
+        WHERE t.user_id = ?
+        GROUP BY t.id, t.name
+        ORDER BY t.name
+    ",
+    )
+    .bind(&session.user_id)
+    .fetch_all(&*db)
+    .await
+    .map_err(|e| format!("Failed to fetch tags: {}", e))?;
+
+    Ok(json!({
+        "tags": tags
+    }))
+}
+
+/// Adds tags to an entry by name, creating any tag that does not exist yet.
+#[tauri::command]
+pub async fn tag_password(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    id: String,
+    names: Vec<String>,
+) -> Result<JsonValue, String> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let user_id = session.user_id;
+    let names = names
+        .iter()
+        .map(|name| clean_name(name))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let existing_password = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM passwords WHERE id = ? AND user_id = ? AND deleted_at IS NULL",
+    )
+    .bind(&id)
+    .bind(&user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to check password: {}", e))?;
+
+    if existing_password == 0 {
+        return Err("Password not found or you don't have permission to tag it".into());
+    }
+
+    revisions::record(&mut tx, &id, &user_id, &session.enc_key).await?;
+    add_tags(&mut tx, &id, &user_id, &names).await?;
+    revisions::record(&mut tx, &id, &user_id, &session.enc_key).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to tag password: {}", e))?;
+
+    let tags = tags_for_passwords(&db, std::slice::from_ref(&id)).await?;
+
+    Ok(json!({
+        "tags": tags.get(&id).cloned().unwrap_or_default(),
+        "message": "Password successfully tagged!"
+    }))
+}
+
+#[tauri::command]
+pub async fn untag_password(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    id: String,
+    tag_id: String,
+) -> Result<JsonValue, String> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let user_id = session.user_id;
+
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let tagged = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM password_tags pt
+         JOIN passwords p ON p.id = pt.password_id
+         WHERE pt.password_id = ? AND pt.tag_id = ? AND p.user_id = ?",
+    )
+    .bind(&id)
+    .bind(&tag_id)
+    .bind(&user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to check tag: {}", e))?;
+
+    if tagged == 0 {
+        return Err("Password does not have that tag".into());
+    }
+
+    revisions::record(&mut tx, &id, &user_id, &session.enc_key).await?;
+
+    sqlx::query("DELETE FROM password_tags WHERE password_id = ? AND tag_id = ?")
+        .bind(&id)
+        .bind(&tag_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to untag password: {}", e))?;
+
+    revisions::record(&mut tx, &id, &user_id, &session.enc_key).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to untag password: {}", e))?;
+
+    Ok(json!({
+        "message": "Tag successfully removed!"
+    }))
+}
+
+#[tauri::command]
+pub async fn rename_tag(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    id: String,
+    name: String,
+) -> Result<JsonValue, String> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let user_id = session.user_id;
+    let name = clean_name(&name)?;
+
+    let taken = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM tags WHERE user_id = ? AND name = ? AND id != ?",
+    )
+    .bind(&user_id)
+    .bind(&name)
+    .bind(&id)
+    .fetch_one(&*db)
+    .await
+    .map_err(|e| format!("Failed to check tag name: {}", e))?;
+
+    if taken > 0 {
+        return Err("A tag with that name already exists, merge the tags instead".into());
+    }
+
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let tagged = tagged_entries(&mut tx, &user_id, std::slice::from_ref(&id)).await?;
+    record_revisions(&mut tx, &tagged, &user_id, &session.enc_key).await?;
+
+    let result =
+        sqlx::query("UPDATE tags SET name = ?, updated_at = ? WHERE id = ? AND user_id = ?")
+            .bind(&name)
+            .bind(Utc::now())
+            .bind(&id)
+            .bind(&user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to rename tag: {}", e))?;
+
+    if result.rows_affected() == 0 {
+        return Err("Tag not found or you don't have permission to rename it".into());
+    }
+
+    record_revisions(&mut tx, &tagged, &user_id, &session.enc_key).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to rename tag: {}", e))?;
+
+    Ok(json!({
+        "message": "Tag successfully renamed!"
+    }))
+}
+
+/// Moves every entry tagged with one of `source_ids` onto `target_id` and deletes the sources.
+#[tauri::command]
+pub async fn merge_tags(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    source_ids: Vec<String>,
+    target_id: String,
+) -> Result<JsonValue, String> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let user_id = session.user_id;
+    let mut source_ids: Vec<String> = source_ids
+        .into_iter()
+        .filter(|id| *id != target_id)
+        .collect();
+    source_ids.sort();
+    source_ids.dedup();
+
+    if source_ids.is_empty() {
+        return Err("Select at least one other tag to merge".into());
+    }
+
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let owned_sql = format!(
+        "SELECT COUNT(*) FROM tags WHERE user_id = ? AND id IN ({})",
+        placeholders(source_ids.len() + 1)
+    );
+    let mut owned_query = sqlx::query_scalar::<_, i64>(&owned_sql)
+        .bind(&user_id)
+        .bind(&target_id);
+    for id in &source_ids {
+        owned_query = owned_query.bind(id);
+    }
+
+    let owned = owned_query
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to check tags: {}", e))?;
+
+    if owned as usize != source_ids.len() + 1 {
+        return Err("Tag not found or you don't have permission to merge it".into());
+    }
+
+    let mut merged_ids = source_ids.clone();
+    merged_ids.push(target_id.clone());
+    let tagged = tagged_entries(&mut tx, &user_id, &merged_ids).await?;
+    record_revisions(&mut tx, &tagged, &user_id, &session.enc_key).await?;
+
+    let relink_sql = format!(
+        "INSERT OR IGNORE INTO password_tags (password_id, tag_id)
+         SELECT password_id, ? FROM password_tags WHERE tag_id IN ({})",
+        placeholders(source_ids.len())
+    );
+    let mut relink_query = sqlx::query(&relink_sql).bind(&target_id);
+    for id in &source_ids {
+        relink_query = relink_query.bind(id);
+    }
+
+    relink_query
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to merge tags: {}", e))?;
+
+    // Links to the source tags are removed through ON DELETE CASCADE
+    let delete_sql = format!(
+        "DELETE FROM tags WHERE user_id = ? AND id IN ({})",
+        placeholders(source_ids.len())
+    );
+    let mut delete_query = sqlx::query(&delete_sql).bind(&user_id);
+    for id in &source_ids {
+        delete_query = delete_query.bind(id);
+    }
+
+    delete_query
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to merge tags: {}", e))?;
+
+    record_revisions(&mut tx, &tagged, &user_id, &session.enc_key).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to merge tags: {}", e))?;
+
+    Ok(json!({
+        "message": "Tags successfully merged!"
+    }))
+}
+
+#[tauri::command]
+pub async fn delete_tag(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    id: String,
+) -> Result<JsonValue, String> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let user_id = session.user_id;
+
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let tagged = tagged_entries(&mut tx, &user_id, std::slice::from_ref(&id)).await?;
+    record_revisions(&mut tx, &tagged, &user_id, &session.enc_key).await?;
+
+    let result = sqlx::query("DELETE FROM tags WHERE id = ? AND user_id = ?")
+        .bind(&id)
+        .bind(&user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to delete tag: {}", e))?;
+
+    if result.rows_affected() == 0 {
+        return Err("Tag not found or you don't have permission to delete it".into());
+    }
+
+    record_revisions(&mut tx, &tagged, &user_id, &session.enc_key).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to delete tag: {}", e))?;
+
+    Ok(json!({
+        "message": "Tag successfully deleted!"
+    }))
+}
@@ -0,0 +1,147 @@
+use crate::{
+    otp::{Otp, OtpKind},
+    revisions, session, DatabasePool, SessionRegistry,
+};
+use chrono::Utc;
+use serde_json::{json, Value as JsonValue};
+use tauri::State;
+
+/// Sets the one-time password secret of an entry from an `otpauth://` or
+/// `steam://` URI or a bare base32 secret. An empty or missing `uri` removes it.
+#[tauri::command]
+pub async fn set_totp(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    id: String,
+    uri: Option<String>,
+) -> Result<JsonValue, String> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let otp = uri
+        .as_deref()
+        .filter(|uri| !uri.trim().is_empty())
+        .map(Otp::parse)
+        .transpose()?;
+    let encrypted_totp = otp
+        .as_ref()
+        .map(|otp| otp.encrypt(&session.user_id, &session.enc_key))
+        .transpose()?;
+
+    let user_id = &session.user_id;
+
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let existing = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM passwords WHERE id = ? AND user_id = ? AND deleted_at IS NULL",
+    )
+    .bind(&id)
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to check password: {}", e))?;
+
+    if existing == 0 {
+        return Err("Password not found".into());
+    }
+
+    revisions::record(&mut tx, &id, user_id, &session.enc_key).await?;
+
+    sqlx::query("UPDATE passwords SET encrypted_totp = ?, updated_at = ? WHERE id = ?")
+        .bind(&encrypted_totp)
+        .bind(Utc::now())
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to save one-time password: {}", e))?;
+
+    revisions::record(&mut tx, &id, user_id, &session.enc_key).await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to save one-time password: {}", e))?;
+
+    Ok(json!({
+        "success": true,
+        "totp": otp.map(|otp| json!({
+            "kind": otp.kind,
+            "issuer": otp.issuer,
+            "account": otp.account,
+            "algorithm": otp.algorithm,
+            "digits": otp.digits,
+            "period": otp.period
+        }))
+    }))
+}
+
+/// Returns the current code of an entry. HOTP secrets move on to the next
+/// counter each time a code is handed out, so they need a writable session.
+#[tauri::command]
+pub async fn get_totp_code(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    id: String,
+) -> Result<JsonValue, String> {
+    let session = session::require_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let mut tx = db
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let encrypted_totp = sqlx::query_scalar::<_, Option<String>>(
+        "SELECT encrypted_totp FROM passwords WHERE id = ? AND user_id = ? AND deleted_at IS NULL",
+    )
+    .bind(&id)
+    .bind(&session.user_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to fetch password: {}", e))?
+    .ok_or("Password not found")?
+    .ok_or("This entry has no one-time password")?;
+
+    let mut otp = Otp::decrypt(&encrypted_totp, &session.enc_key)?;
+    let now = Utc::now().timestamp();
+    let code = otp.code_at(now)?;
+
+    if otp.kind == OtpKind::Hotp {
+        session::require_writable_session(&sessions, &session_token)?;
+
+        otp.counter += 1;
+        let next_totp = otp.encrypt(&session.user_id, &session.enc_key)?;
+
+        // Only moves on from the counter read above, so a code is never handed out twice
+        let updated = sqlx::query(
+            "UPDATE passwords SET encrypted_totp = ? WHERE id = ? AND user_id = ? AND encrypted_totp = ?",
+        )
+        .bind(&next_totp)
+        .bind(&id)
+        .bind(&session.user_id)
+        .bind(&encrypted_totp)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to save one-time password: {}", e))?
+        .rows_affected();
+
+        if updated == 0 {
+            return Err("The one-time password was just used, try again".into());
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| format!("Failed to save one-time password: {}", e))?;
+    }
+
+    Ok(json!({
+        "code": code,
+        "kind": otp.kind,
+        "remaining_seconds": otp.remaining_seconds(now),
+        "period": (otp.kind != OtpKind::Hotp).then_some(otp.period)
+    }))
+}
@@ -0,0 +1,112 @@
+use crate::{
+    config::AppConfig, error::CommandError, models::ItemType, session, trash, DatabasePool,
+    SessionRegistry,
+};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value as JsonValue};
+use tauri::State;
+
+#[tauri::command]
+pub async fn list_trash(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    config: State<'_, AppConfig>,
+    session_token: String,
+) -> Result<JsonValue, String> {
+    let session = session::require_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let retention_days = config.trash_retention_days;
+
+    // Don't show entries the hourly purge hasn't got to yet
+    if retention_days > 0 {
+        trash::purge_expired(&*db, retention_days).await?;
+    }
+
+    let entries = sqlx::query_as::<_, (String, String, ItemType, DateTime<Utc>)>(
+        "SELECT id, website, item_type, deleted_at
+         FROM passwords
+         WHERE user_id = ? AND deleted_at IS NOT NULL
+         ORDER BY deleted_at DESC",
+    )
+    .bind(&session.user_id)
+    .fetch_all(&*db)
+    .await
+    .map_err(|e| format!("Failed to fetch trash: {}", e))?;
+
+    let entries: Vec<JsonValue> = entries
+        .into_iter()
+        .map(|(id, website, item_type, deleted_at)| {
+            let purge_at = (retention_days > 0)
+                .then(|| (deleted_at + chrono::Duration::days(retention_days)).to_rfc3339());
+
+            json!({
+                "id": id,
+                "website": website,
+                "item_type": item_type,
+                "deleted_at": deleted_at.to_rfc3339(),
+                "purge_at": purge_at
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "entries": entries,
+        "count": entries.len()
+    }))
+}
+
+#[tauri::command]
+pub async fn restore_entry(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    session_token: String,
+    id: String,
+) -> Result<JsonValue, String> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+
+    let restored = sqlx::query(
+        "UPDATE passwords SET deleted_at = NULL
+         WHERE id = ? AND user_id = ? AND deleted_at IS NOT NULL",
+    )
+    .bind(&id)
+    .bind(&session.user_id)
+    .execute(&*db)
+    .await
+    .map_err(|e| format!("Failed to restore entry: {}", e))?
+    .rows_affected();
+
+    if restored == 0 {
+        return Err("Entry not found in the trash".into());
+    }
+
+    Ok(json!({
+        "message": "Entry successfully restored!"
+    }))
+}
+
+/// Permanently deletes everything in the trash.
+#[tauri::command]
+pub async fn empty_trash(
+    sessions: State<'_, SessionRegistry>,
+    pool: State<'_, DatabasePool>,
+    config: State<'_, AppConfig>,
+    session_token: String,
+) -> Result<JsonValue, CommandError> {
+    let session = session::require_writable_session(&sessions, &session_token)?;
+    let db = pool.for_session(&session)?;
+    session::require_recent_verification(&session, config.reauth_window_minutes)?;
+
+    let deleted = sqlx::query("DELETE FROM passwords WHERE user_id = ? AND deleted_at IS NOT NULL")
+        .bind(&session.user_id)
+        .execute(&*db)
+        .await
+        .map_err(|e| format!("Failed to empty trash: {}", e))?
+        .rows_affected();
+
+    Ok(json!({
+        "deleted": deleted,
+        "message": format!("Permanently deleted {} entries", deleted)
+    }))
+}
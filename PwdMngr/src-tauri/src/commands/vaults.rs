@@ -0,0 +1,151 @@
+use crate::{data_dir::DataDir, db, session, vault, DatabasePool, SessionRegistry};
+use serde_json::{json, Value as JsonValue};
+use tauri::State;
+
+#[tauri::command]
+pub async fn list_vaults(
+    data_dir: State<'_, DataDir>,
+    pool: State<'_, DatabasePool>,
+    sessions: State<'_, SessionRegistry>,
+    session_token: String,
+) -> Result<JsonValue, String> {
+    session::require_session(&sessions, &session_token)?;
+
+    let (open, active) = {
+        let vaults = pool.0.read().unwrap();
+        let mut open: Vec<String> = vaults
+            .open
+            .keys()
+            .map(|path| path.display().to_string())
+            .collect();
+        open.sort();
+        let active = vaults
+            .active
+            .as_ref()
+            .map(|path| path.display().to_string());
+        (open, active)
+    };
+
+    Ok(json!({
+        "open": open,
+        "active": active,
+        "recent": vault::load_recent_vaults(&data_dir.path)
+    }))
+}
+
+#[tauri::command]
+pub async fn create_vault(
+    data_dir: State<'_, DataDir>,
+    pool: State<'_, DatabasePool>,
+    sessions: State<'_, SessionRegistry>,
+    session_token: String,
+    path: String,
+) -> Result<JsonValue, String> {
+    session::require_writable_session(&sessions, &session_token)?;
+    let vault_path = vault::resolve_vault_path(&path, false)?;
+
+    if !data_dir.allows(&vault_path) {
+        return Err("Portable mode only allows vaults inside the data directory".into());
+    }
+
+    if vault_path.exists() {
+        return Err("A file already exists at that path".into());
+    }
+
+    let vault_pool = db::connect(&vault_path, true)
+        .await
+        .map_err(|e| format!("Failed to create vault: {}", e))?;
+
+    pool.0
+        .write()
+        .unwrap()
+        .activate(vault_path.clone(), Some(vault_pool));
+    vault::remember_vault(&data_dir.path, &vault_path)?;
+
+    Ok(json!({
+        "active": vault_path.display().to_string(),
+        "message": "Vault successfully created!"
+    }))
+}
+
+#[tauri::command]
+pub async fn open_vault(
+    data_dir: State<'_, DataDir>,
+    pool: State<'_, DatabasePool>,
+    sessions: State<'_, SessionRegistry>,
+    session_token: String,
+    path: String,
+) -> Result<JsonValue, String> {
+    session::require_session(&sessions, &session_token)?;
+    let vault_path = vault::resolve_vault_path(&path, true)?;
+
+    if !data_dir.allows(&vault_path) {
+        return Err("Portable mode only allows vaults inside the data directory".into());
+    }
+
+    let already_open = pool.0.read().unwrap().open.contains_key(&vault_path);
+    let vault_pool = if already_open {
+        None
+    } else {
+        Some(
+            db::connect(&vault_path, false)
+                .await
+                .map_err(|e| format!("Failed to open vault: {}", e))?,
+        )
+    };
+
+    pool.0
+        .write()
+        .unwrap()
+        .activate(vault_path.clone(), vault_pool);
+    vault::remember_vault(&data_dir.path, &vault_path)?;
+
+    Ok(json!({
+        "active": vault_path.display().to_string(),
+        "message": "Vault successfully opened!"
+    }))
+}
+
+#[tauri::command]
+pub async fn switch_vault(
+    pool: State<'_, DatabasePool>,
+    sessions: State<'_, SessionRegistry>,
+    session_token: String,
+    path: String,
+) -> Result<JsonValue, String> {
+    session::require_writable_session(&sessions, &session_token)?;
+    let vault_path = vault::resolve_vault_path(&path, true)?;
+
+    if !pool.0.read().unwrap().open.contains_key(&vault_path) {
+        return Err("Vault is not open".into());
+    }
+
+    pool.0.write().unwrap().activate(vault_path.clone(), None);
+
+    Ok(json!({
+        "active": vault_path.display().to_string(),
+        "message": "Switched vault!"
+    }))
+}
+
+#[tauri::command]
+pub async fn close_vault(
+    pool: State<'_, DatabasePool>,
+    sessions: State<'_, SessionRegistry>,
+    session_token: String,
+    path: String,
+) -> Result<JsonValue, String> {
+    session::require_writable_session(&sessions, &session_token)?;
+    let vault_path = vault::resolve_vault_path(&path, true)?;
+
+    let closed_pool = pool.0.write().unwrap().close(&vault_path)?;
+
+    // Sessions of other vaults keep working
+    session::end_vault_sessions(&sessions, &vault_path);
+
+    closed_pool.close().await;
+
+    Ok(json!({
+        "message": "Vault closed!"
+    }))
+}
@@ -0,0 +1,52 @@
+use crate::password_policy::PasswordPolicy;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    pub reauth_window_minutes: i64,
+    /// Lock idle sessions after this many minutes, 0 disables auto-lock.
+    pub auto_lock_minutes: i64,
+    /// End sessions this many hours after login, 0 keeps them until logout.
+    pub session_lifetime_hours: i64,
+    pub password_policy: PasswordPolicy,
+    /// Largest file that can be attached to an entry.
+    pub max_attachment_mb: u64,
+    /// Limit for all attachments of a single entry together.
+    pub max_entry_attachments_mb: u64,
+    /// Previous passwords kept per entry, 0 keeps none.
+    pub password_history_limit: i64,
+    /// Purge entries from the trash after this many days, 0 keeps them until emptied.
+    pub trash_retention_days: i64,
+    /// Entries due for rotation within this many days are listed as due soon.
+    pub rotation_warning_days: i64,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            reauth_window_minutes: 5,
+            auto_lock_minutes: 0,
+            session_lifetime_hours: 24,
+            password_policy: PasswordPolicy::default(),
+            max_attachment_mb: 25,
+            max_entry_attachments_mb: 100,
+            password_history_limit: 10,
+            trash_retention_days: 30,
+            rotation_warning_days: 14,
+        }
+    }
+}
+
+pub fn load_config(data_dir: &Path) -> AppConfig {
+    let config_path = data_dir.join("config.json");
+
+    match std::fs::read_to_string(&config_path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            eprintln!("Invalid config file {}: {}", config_path.display(), e);
+            AppConfig::default()
+        }),
+        Err(_) => AppConfig::default(),
+    }
+}
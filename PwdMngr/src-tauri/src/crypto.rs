@@ -28,6 +28,7 @@ pub enum CryptoError {
 
 const KEY_LEN: usize = 32;
 const SALT_LEN: usize = 16;
+const SESSION_TOKEN_LEN: usize = 32;
 
 pub fn hash_password(password: &str) -> Result<String, CryptoError> {
     let rng = ring::rand::SystemRandom::new();
@@ -80,6 +81,15 @@ pub fn generate_encryption_key(password: &str) -> Result<String, CryptoError> {
     Ok(BASE64.encode(result))
 }
 
+pub fn generate_session_token() -> Result<String, CryptoError> {
+    let rng = rand::SystemRandom::new();
+    let mut token_bytes = [0u8; SESSION_TOKEN_LEN];
+    rand::SecureRandom::fill(&rng, &mut token_bytes)
+        .map_err(|_| CryptoError::KeyDerivationError("Failed to generate session token".into()))?;
+
+    Ok(BASE64.encode(token_bytes))
+}
+
 pub fn verify_password(password: &str, stored_hash: &str) -> Result<bool, CryptoError> {
     let parsed_hash = PasswordHash::new(stored_hash)
         .map_err(|e| CryptoError::HashingError(format!("Invalid password hash: {}", e)))?;
@@ -192,3 +202,55 @@ pub fn decrypt(encrypted_data: &str, encryption_key: &str) -> Result<String, Cry
     String::from_utf8(plaintext.to_vec())
         .map_err(|_| CryptoError::DecryptionError("Decrypted data is not valid UTF-8".into()))
 }
+
+/// Encrypts raw bytes, binding them to `aad` so they can't be moved to another
+/// place where different associated data is expected. Returns the nonce followed
+/// by the ciphertext.
+pub fn encrypt_bytes(
+    input: &[u8],
+    aad: &[u8],
+    encryption_key: &str,
+) -> Result<Vec<u8>, CryptoError> {
+    let key = process_encryption_key(encryption_key, true)?;
+
+    let rng = rand::SystemRandom::new();
+    let mut nonce_bytes = [0u8; 12];
+    rand::SecureRandom::fill(&rng, &mut nonce_bytes)
+        .map_err(|_| CryptoError::EncryptionError("Failed to generate nonce".into()))?;
+
+    let nonce = Nonce::assume_unique_for_key(nonce_bytes);
+
+    let mut in_out = input.to_vec();
+    key.seal_in_place_append_tag(nonce, aead::Aad::from(aad), &mut in_out)
+        .map_err(|_| CryptoError::EncryptionError("Encryption failed!".into()))?;
+
+    let mut result = Vec::with_capacity(nonce_bytes.len() + in_out.len());
+    result.extend_from_slice(&nonce_bytes);
+    result.extend_from_slice(&in_out);
+
+    Ok(result)
+}
+
+pub fn decrypt_bytes(
+    encrypted_data: &[u8],
+    aad: &[u8],
+    encryption_key: &str,
+) -> Result<Vec<u8>, CryptoError> {
+    if encrypted_data.len() < 12 + AES_256_GCM.tag_len() {
+        return Err(CryptoError::DecryptionError(
+            "Encrypted data is too short".into(),
+        ));
+    }
+
+    let key = process_encryption_key(encryption_key, false)?;
+
+    let nonce = Nonce::assume_unique_for_key(*array_ref![encrypted_data, 0, 12]);
+
+    let mut ciphertext = encrypted_data[12..].to_vec();
+
+    let plaintext = key
+        .open_in_place(nonce, aead::Aad::from(aad), &mut ciphertext)
+        .map_err(|_| CryptoError::DecryptionError("Decryption failed".into()))?;
+
+    Ok(plaintext.to_vec())
+}
@@ -0,0 +1,138 @@
+use crate::{
+    crypto,
+    models::{CustomField, CustomFieldRecord, CustomFieldType},
+};
+use chrono::Utc;
+use sqlx::{Sqlite, SqliteExecutor, Transaction};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+const MAX_FIELDS: usize = 50;
+const MAX_NAME_LENGTH: usize = 100;
+
+pub fn validate(fields: &[CustomField]) -> Result<(), String> {
+    if fields.len() > MAX_FIELDS {
+        return Err(format!("An entry can have at most {} custom fields", MAX_FIELDS));
+    }
+
+    for field in fields {
+        let name = field.name.trim();
+
+        if name.is_empty() {
+            return Err("Custom field name cannot be empty".into());
+        }
+
+        if name.chars().count() > MAX_NAME_LENGTH {
+            return Err(format!(
+                "Custom field name must be at most {} characters",
+                MAX_NAME_LENGTH
+            ));
+        }
+
+        match field.field_type {
+            CustomFieldType::Boolean if field.value != "true" && field.value != "false" => {
+                return Err(format!("Custom field {} must be true or false", name));
+            }
+            CustomFieldType::Url if !field.value.is_empty() => {
+                url::Url::parse(&field.value)
+                    .map_err(|_| format!("Custom field {} must be a valid URL", name))?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Replaces the custom fields of an entry, keeping them in the order given.
+pub async fn replace_fields(
+    tx: &mut Transaction<'_, Sqlite>,
+    password_id: &str,
+    user_id: &str,
+    enc_key: &str,
+    fields: &[CustomField],
+) -> Result<(), String> {
+    sqlx::query("DELETE FROM custom_fields WHERE password_id = ?")
+        .bind(password_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to update custom fields: {}", e))?;
+
+    let now = Utc::now();
+
+    for (position, field) in fields.iter().enumerate() {
+        // Empty values are stored as-is, there is nothing to encrypt
+        let encrypted_value = if field.value.is_empty() {
+            String::new()
+        } else {
+            crypto::encrypt(&field.value, user_id, enc_key)
+                .map_err(|e| format!("Failed to encrypt custom field: {}", e))?
+        };
+
+        sqlx::query("INSERT INTO custom_fields (id, password_id, position, name, field_type, encrypted_value, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)")
+            .bind(Uuid::new_v4().to_string())
+            .bind(password_id)
+            .bind(position as i64)
+            .bind(field.name.trim())
+            .bind(field.field_type)
+            .bind(&encrypted_value)
+            .bind(now)
+            .bind(now)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| format!("Failed to save custom field: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Returns the decrypted custom fields of each listed entry, keyed by entry id.
+pub async fn load_fields<'e, E>(
+    executor: E,
+    password_ids: &[String],
+    enc_key: &str,
+) -> Result<HashMap<String, Vec<CustomField>>, String>
+where
+    E: SqliteExecutor<'e>,
+{
+    let mut fields: HashMap<String, Vec<CustomField>> = HashMap::new();
+
+    if password_ids.is_empty() {
+        return Ok(fields);
+    }
+
+    let sql = format!(
+        "SELECT id, password_id, position, name, field_type, encrypted_value
+         FROM custom_fields
+         WHERE password_id IN ({})
+         ORDER BY password_id, position",
+        vec!["?"; password_ids.len()].join(", ")
+    );
+
+    let mut query = sqlx::query_as::<_, CustomFieldRecord>(&sql);
+    for id in password_ids {
+        query = query.bind(id);
+    }
+
+    let records = query
+        .fetch_all(executor)
+        .await
+        .map_err(|e| format!("Failed to fetch custom fields: {}", e))?;
+
+    for record in records {
+        let value = if record.encrypted_value.is_empty() {
+            String::new()
+        } else {
+            crypto::decrypt(&record.encrypted_value, enc_key)
+                .map_err(|e| format!("Failed to decrypt custom field: {}", e))?
+        };
+
+        fields.entry(record.password_id).or_default().push(CustomField {
+            name: record.name,
+            field_type: record.field_type,
+            value,
+        });
+    }
+
+    Ok(fields)
+}
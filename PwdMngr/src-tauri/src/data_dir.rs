@@ -0,0 +1,68 @@
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+pub const DATA_DIR_ARG: &str = "--data-dir";
+pub const DATA_DIR_ENV: &str = "PWDMNGR_DATA_DIR";
+pub const PORTABLE_MARKER: &str = "portable";
+
+/// Directory holding the config, the default vault and everything else the app writes.
+#[derive(Debug, Clone)]
+pub struct DataDir {
+    pub path: PathBuf,
+    /// Set when a `portable` marker file sits next to the binary. Nothing may
+    /// then be written outside `path`.
+    pub portable: bool,
+}
+
+impl DataDir {
+    /// Picks the data directory from, in order: the `--data-dir` argument, the
+    /// `PWDMNGR_DATA_DIR` environment variable, a `portable` marker next to the
+    /// binary (using `data/` beside it), or the platform app data directory.
+    pub fn resolve(app: &AppHandle) -> Result<DataDir, String> {
+        let (path, portable) = if let Some(path) = data_dir_from_args(std::env::args()) {
+            (path, false)
+        } else if let Some(path) = std::env::var_os(DATA_DIR_ENV).filter(|v| !v.is_empty()) {
+            (PathBuf::from(path), false)
+        } else if let Some(exe_dir) = portable_exe_dir() {
+            (exe_dir.join("data"), true)
+        } else {
+            let path = app
+                .path()
+                .app_data_dir()
+                .map_err(|e| format!("Failed to get app directory: {}", e))?;
+            (path, false)
+        };
+
+        std::fs::create_dir_all(&path)
+            .map_err(|e| format!("Failed to create data directory {}: {}", path.display(), e))?;
+        let path = path
+            .canonicalize()
+            .map_err(|e| format!("Invalid data directory {}: {}", path.display(), e))?;
+
+        Ok(DataDir { path, portable })
+    }
+
+    /// Whether the app may write to `path`; always true outside portable mode.
+    pub fn allows(&self, path: &Path) -> bool {
+        !self.portable || path.starts_with(&self.path)
+    }
+}
+
+fn data_dir_from_args(args: impl Iterator<Item = String>) -> Option<PathBuf> {
+    let mut args = args.skip(1);
+    while let Some(arg) = args.next() {
+        if arg == DATA_DIR_ARG {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.strip_prefix(DATA_DIR_ARG).and_then(|v| v.strip_prefix('=')) {
+            return Some(PathBuf::from(path));
+        }
+    }
+
+    None
+}
+
+fn portable_exe_dir() -> Option<PathBuf> {
+    let exe_dir = std::env::current_exe().ok()?.parent()?.to_path_buf();
+    exe_dir.join(PORTABLE_MARKER).is_file().then_some(exe_dir)
+}
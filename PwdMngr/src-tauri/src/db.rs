@@ -1,23 +1,34 @@
-use sqlx::{migrate::MigrateDatabase, sqlite::SqlitePoolOptions, SqlitePool};
-use tauri::{AppHandle, Manager};
-
-pub async fn establish_connection(app: &AppHandle) -> Result<SqlitePool, sqlx::Error> {
-    let app_dir = app
-        .path()
-        .app_data_dir()
-        .expect("Failed to get app directory");
-    std::fs::create_dir_all(&app_dir).expect("Failed to create directory");
+use sqlx::{
+    migrate::MigrateDatabase,
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    SqlitePool,
+};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+pub fn default_vault_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("passwords.db")
+}
 
-    let db_path = app_dir.join("passwords.db");
+pub async fn connect(db_path: &Path, create: bool) -> Result<SqlitePool, sqlx::Error> {
     let db_url = format!("sqlite:{}", db_path.display());
 
     if !sqlx::Sqlite::database_exists(&db_url).await? {
+        if !create {
+            return Err(sqlx::Error::Configuration(
+                format!("Vault {} does not exist", db_path.display()).into(),
+            ));
+        }
         sqlx::Sqlite::create_database(&db_url).await?
     }
 
+    // Overwrite deleted content with zeros so removed entries and accounts
+    // cannot be recovered from freed pages.
+    let options = SqliteConnectOptions::from_str(&db_url)?.pragma("secure_delete", "ON");
+
     let pool = SqlitePoolOptions::new()
         .max_connections(5)
-        .connect(&db_url)
+        .connect_with(options)
         .await?;
 
     run_migrations(&pool).await?;
@@ -25,6 +36,17 @@ pub async fn establish_connection(app: &AppHandle) -> Result<SqlitePool, sqlx::E
     Ok(pool)
 }
 
+/// Copies the write-ahead log into the database and empties it, so deleted
+/// content doesn't linger in the `-wal` file after `secure_delete` zeroed it in
+/// the database itself.
+pub async fn truncate_wal(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
 async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
     sqlx::migrate!("./migrations").run(pool).await?;
 
@@ -0,0 +1,426 @@
+use crate::models::{EntryUrl, EntryUrlRecord, UrlMatch};
+use regex::Regex;
+use sqlx::{Sqlite, SqliteExecutor, Transaction};
+use std::collections::HashMap;
+use url::Url;
+
+const MAX_URLS: usize = 50;
+const MAX_URL_LENGTH: usize = 2048;
+
+/// Trims the URLs, drops empty ones and rejects URLs that can't be used with
+/// their match mode. URLs in `existing`, the ones already stored on the entry,
+/// are kept as they are: entries from before URLs were validated may hold
+/// free-form text.
+pub fn normalize(urls: Vec<EntryUrl>, existing: &[EntryUrl]) -> Result<Vec<EntryUrl>, String> {
+    let mut normalized = Vec::with_capacity(urls.len());
+
+    for mut entry_url in urls {
+        entry_url.url = entry_url.url.trim().to_string();
+        if entry_url.url.is_empty() {
+            continue;
+        }
+
+        if entry_url.url.chars().count() > MAX_URL_LENGTH {
+            return Err(format!(
+                "URLs must be at most {} characters",
+                MAX_URL_LENGTH
+            ));
+        }
+
+        let unchanged = existing.iter().any(|stored| {
+            stored.url.trim() == entry_url.url && stored.match_mode == entry_url.match_mode
+        });
+
+        match entry_url.match_mode {
+            _ if unchanged => {}
+            // Never matched, so any text can be kept for reference
+            UrlMatch::Never => {}
+            UrlMatch::Regex => {
+                Regex::new(&entry_url.url)
+                    .map_err(|_| format!("{} is not a valid regular expression", entry_url.url))?;
+            }
+            _ if parse_url(&entry_url.url).is_none() => {
+                return Err(format!("{} is not a valid URL", entry_url.url));
+            }
+            _ => {}
+        }
+
+        normalized.push(entry_url);
+    }
+
+    if normalized.len() > MAX_URLS {
+        return Err(format!("An entry can have at most {} URLs", MAX_URLS));
+    }
+
+    Ok(normalized)
+}
+
+/// The URL shown and opened for an entry: the first one that is an address
+/// rather than a pattern.
+pub fn primary(urls: &[EntryUrl]) -> Option<&str> {
+    urls.iter()
+        .find(|entry_url| entry_url.match_mode != UrlMatch::Regex)
+        .map(|entry_url| entry_url.url.as_str())
+}
+
+/// Applies the single `website_url` of callers that don't know about URL lists
+/// to the primary URL, keeping its match mode and every other URL. Text that
+/// isn't an address, which the single field used to accept, is kept in
+/// `Never` mode.
+pub fn with_primary(mut urls: Vec<EntryUrl>, website_url: Option<String>) -> Vec<EntryUrl> {
+    let website_url = website_url
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty());
+    let index = urls
+        .iter()
+        .position(|entry_url| entry_url.match_mode != UrlMatch::Regex);
+
+    match (index, website_url) {
+        (Some(index), Some(url)) => {
+            if urls[index].url.trim() != url && parse_url(&url).is_none() {
+                urls[index].match_mode = UrlMatch::Never;
+            }
+            urls[index].url = url;
+        }
+        (Some(index), None) => {
+            urls.remove(index);
+        }
+        (None, Some(url)) => {
+            let match_mode = if parse_url(&url).is_some() {
+                UrlMatch::default()
+            } else {
+                UrlMatch::Never
+            };
+            urls.insert(0, EntryUrl { url, match_mode });
+        }
+        (None, None) => {}
+    }
+
+    urls
+}
+
+/// A page address, parsed once to check against many entry URLs.
+pub struct Page<'a> {
+    raw: &'a str,
+    url: Option<Url>,
+}
+
+impl<'a> Page<'a> {
+    pub fn new(page: &'a str) -> Self {
+        let raw = page.trim();
+        Self {
+            raw,
+            url: parse_url(raw),
+        }
+    }
+}
+
+/// An entry URL with its address or regular expression parsed once, to check
+/// against pages.
+pub struct UrlMatcher<'a> {
+    entry_url: &'a EntryUrl,
+    url: Option<Url>,
+    regex: Option<Regex>,
+}
+
+impl<'a> UrlMatcher<'a> {
+    pub fn new(entry_url: &'a EntryUrl) -> Self {
+        let (url, regex) = match entry_url.match_mode {
+            UrlMatch::Never => (None, None),
+            UrlMatch::Regex => (None, Regex::new(&entry_url.url).ok()),
+            _ => (parse_url(&entry_url.url), None),
+        };
+
+        Self {
+            entry_url,
+            url,
+            regex,
+        }
+    }
+
+    pub fn entry_url(&self) -> &'a EntryUrl {
+        self.entry_url
+    }
+
+    /// Whether the entry URL should be offered for `page`.
+    pub fn matches(&self, page: &Page) -> bool {
+        match self.entry_url.match_mode {
+            UrlMatch::Never => false,
+            UrlMatch::Regex => self
+                .regex
+                .as_ref()
+                .is_some_and(|regex| regex.is_match(page.raw)),
+            UrlMatch::StartsWith => page.raw.starts_with(&self.entry_url.url),
+            UrlMatch::Exact => match (&self.url, &page.url) {
+                (Some(expected), Some(page)) => expected == page,
+                _ => self.entry_url.url == page.raw,
+            },
+            UrlMatch::Host => match (&self.url, &page.url) {
+                (Some(expected), Some(page)) => {
+                    expected.host_str() == page.host_str()
+                        && expected.port_or_known_default() == page.port_or_known_default()
+                }
+                _ => false,
+            },
+            UrlMatch::BaseDomain => match (&self.url, &page.url) {
+                (Some(expected), Some(page)) => base_domain(expected) == base_domain(page),
+                _ => false,
+            },
+        }
+    }
+}
+
+/// Accepts addresses without a scheme, like `example.com`, as https.
+fn parse_url(url: &str) -> Option<Url> {
+    let parsed = if url.contains("://") {
+        Url::parse(url)
+    } else {
+        Url::parse(&format!("https://{}", url))
+    };
+
+    parsed.ok().filter(|url| url.host_str().is_some())
+}
+
+/// The registrable domain of a URL, or its host for IP addresses and names
+/// without a public suffix such as `localhost`.
+fn base_domain(url: &Url) -> Option<&str> {
+    match url.domain() {
+        Some(domain) => Some(psl::domain_str(domain).unwrap_or(domain)),
+        None => url.host_str(),
+    }
+}
+
+/// Replaces the URLs of an entry, keeping them in the order given.
+pub async fn replace_urls(
+    tx: &mut Transaction<'_, Sqlite>,
+    password_id: &str,
+    urls: &[EntryUrl],
+) -> Result<(), String> {
+    sqlx::query("DELETE FROM entry_urls WHERE password_id = ?")
+        .bind(password_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to update URLs: {}", e))?;
+
+    for (position, entry_url) in urls.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO entry_urls (password_id, position, url, match_mode) VALUES (?, ?, ?, ?)",
+        )
+        .bind(password_id)
+        .bind(position as i64)
+        .bind(&entry_url.url)
+        .bind(entry_url.match_mode)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to save URL: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Returns the URLs of each listed entry, keyed by entry id.
+pub async fn load_urls<'e, E>(
+    executor: E,
+    password_ids: &[String],
+) -> Result<HashMap<String, Vec<EntryUrl>>, String>
+where
+    E: SqliteExecutor<'e>,
+{
+    let mut urls: HashMap<String, Vec<EntryUrl>> = HashMap::new();
+
+    if password_ids.is_empty() {
+        return Ok(urls);
+    }
+
+    let sql = format!(
+        "SELECT password_id, url, match_mode
+         FROM entry_urls
+         WHERE password_id IN ({})
+         ORDER BY password_id, position",
+        vec!["?"; password_ids.len()].join(", ")
+    );
+
+    let mut query = sqlx::query_as::<_, EntryUrlRecord>(&sql);
+    for id in password_ids {
+        query = query.bind(id);
+    }
+
+    let records = query
+        .fetch_all(executor)
+        .await
+        .map_err(|e| format!("Failed to fetch URLs: {}", e))?;
+
+    for record in records {
+        urls.entry(record.password_id).or_default().push(EntryUrl {
+            url: record.url,
+            match_mode: record.match_mode,
+        });
+    }
+
+    Ok(urls)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry_url(url: &str, match_mode: UrlMatch) -> EntryUrl {
+        EntryUrl {
+            url: url.into(),
+            match_mode,
+        }
+    }
+
+    fn matches(url: &str, match_mode: UrlMatch, page: &str) -> bool {
+        UrlMatcher::new(&entry_url(url, match_mode)).matches(&Page::new(page))
+    }
+
+    #[test]
+    fn base_domain_matches_subdomains() {
+        assert!(matches(
+            "https://login.example.co.uk/x",
+            UrlMatch::BaseDomain,
+            "https://www.example.co.uk/"
+        ));
+        assert!(matches(
+            "example.com",
+            UrlMatch::BaseDomain,
+            "https://a.b.example.com/login"
+        ));
+    }
+
+    #[test]
+    fn base_domain_rejects_look_alikes() {
+        for page in [
+            "https://example.com.evil.net/",
+            "https://myexample.com/",
+            "https://example.org/",
+            "https://other.co.uk/",
+        ] {
+            assert!(
+                !matches("https://example.com", UrlMatch::BaseDomain, page),
+                "{}",
+                page
+            );
+        }
+        assert!(!matches(
+            "https://example.co.uk",
+            UrlMatch::BaseDomain,
+            "https://other.co.uk/"
+        ));
+    }
+
+    #[test]
+    fn host_requires_same_host_and_port() {
+        assert!(matches(
+            "https://app.example.com/a",
+            UrlMatch::Host,
+            "https://app.example.com:443/b"
+        ));
+        assert!(!matches(
+            "https://app.example.com",
+            UrlMatch::Host,
+            "https://www.example.com/"
+        ));
+        assert!(!matches(
+            "https://app.example.com",
+            UrlMatch::Host,
+            "https://app.example.com:8443/"
+        ));
+    }
+
+    #[test]
+    fn starts_with_compares_text() {
+        assert!(matches(
+            "https://example.com/admin",
+            UrlMatch::StartsWith,
+            "https://example.com/admin/users"
+        ));
+        assert!(!matches(
+            "https://example.com/admin",
+            UrlMatch::StartsWith,
+            "https://example.com/"
+        ));
+    }
+
+    #[test]
+    fn exact_compares_parsed_urls() {
+        assert!(matches(
+            "https://example.com/login",
+            UrlMatch::Exact,
+            "https://EXAMPLE.com/login"
+        ));
+        assert!(!matches(
+            "https://example.com/login",
+            UrlMatch::Exact,
+            "https://example.com/login?next=/"
+        ));
+    }
+
+    #[test]
+    fn regex_matches_pattern() {
+        assert!(matches(
+            r"^https://(eu|us)\.example\.com/",
+            UrlMatch::Regex,
+            "https://eu.example.com/home"
+        ));
+        assert!(!matches(
+            r"^https://(eu|us)\.example\.com/",
+            UrlMatch::Regex,
+            "https://asia.example.com/home"
+        ));
+        assert!(!matches("(", UrlMatch::Regex, "("));
+    }
+
+    #[test]
+    fn never_does_not_match() {
+        assert!(!matches(
+            "https://example.com",
+            UrlMatch::Never,
+            "https://example.com"
+        ));
+    }
+
+    #[test]
+    fn normalize_rejects_new_invalid_urls() {
+        assert!(normalize(vec![entry_url("My Bank (old)", UrlMatch::BaseDomain)], &[]).is_err());
+        assert!(normalize(vec![entry_url("(", UrlMatch::Regex)], &[]).is_err());
+        assert!(normalize(vec![entry_url("My Bank (old)", UrlMatch::Never)], &[]).is_ok());
+    }
+
+    #[test]
+    fn normalize_keeps_stored_urls() {
+        let stored = vec![entry_url("My Bank (old)", UrlMatch::BaseDomain)];
+
+        let urls = normalize(
+            vec![
+                entry_url(" My Bank (old) ", UrlMatch::BaseDomain),
+                entry_url("", UrlMatch::Host),
+            ],
+            &stored,
+        )
+        .unwrap();
+
+        assert_eq!(urls.len(), 1);
+        assert_eq!(urls[0].url, "My Bank (old)");
+    }
+
+    #[test]
+    fn with_primary_keeps_free_form_text_in_never_mode() {
+        let urls = with_primary(Vec::new(), Some("My Bank (old)".into()));
+        assert_eq!(urls[0].match_mode, UrlMatch::Never);
+
+        let urls = with_primary(
+            vec![entry_url("https://a.com", UrlMatch::Host)],
+            Some("not a url".into()),
+        );
+        assert_eq!(urls[0].match_mode, UrlMatch::Never);
+
+        let urls = with_primary(
+            vec![entry_url("https://a.com", UrlMatch::Host)],
+            Some("https://b.com".into()),
+        );
+        assert_eq!(urls[0].match_mode, UrlMatch::Host);
+        assert_eq!(primary(&urls), Some("https://b.com"));
+    }
+}
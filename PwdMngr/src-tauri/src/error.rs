@@ -0,0 +1,58 @@
+use crate::password_policy::PolicyViolation;
+use serde::Serialize;
+use thiserror::Error;
+
+#[derive(Debug, Error, Serialize)]
+#[serde(tag = "kind")]
+pub enum CommandError {
+    #[error("{message}")]
+    Failed { message: String },
+
+    #[error("{message}")]
+    ReauthRequired { message: String, window_minutes: i64 },
+
+    #[error("{message}")]
+    PasswordPolicy {
+        message: String,
+        violations: Vec<PolicyViolation>,
+    },
+}
+
+impl CommandError {
+    pub fn reauth_required(window_minutes: i64) -> Self {
+        CommandError::ReauthRequired {
+            message: format!(
+                "Please re-enter your master password, this action requires verification within the last {} minutes",
+                window_minutes
+            ),
+            window_minutes,
+        }
+    }
+
+    pub fn password_policy(violations: Vec<PolicyViolation>) -> Self {
+        let message = violations
+            .iter()
+            .map(|violation| violation.message.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        CommandError::PasswordPolicy {
+            message,
+            violations,
+        }
+    }
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::Failed { message }
+    }
+}
+
+impl From<&str> for CommandError {
+    fn from(message: &str) -> Self {
+        CommandError::Failed {
+            message: message.to_string(),
+        }
+    }
+}
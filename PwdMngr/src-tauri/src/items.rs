@@ -0,0 +1,86 @@
+use crate::{crypto, models::ItemType};
+use serde::{Deserialize, Serialize};
+
+pub mod api_credential;
+pub mod card;
+pub mod identity;
+pub mod ssh_key;
+
+use api_credential::ApiCredential;
+use card::Card;
+use identity::Identity;
+use ssh_key::SshKey;
+
+const MAX_NOTE_LENGTH: usize = 100_000;
+
+/// Fields of entries that are not plain logins. Stored as encrypted JSON in
+/// `passwords.encrypted_data`, with the entry title kept in `website`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ItemData {
+    SecureNote { body: String },
+    Card(Card),
+    Identity(Identity),
+    SshKey(SshKey),
+    ApiCredential(ApiCredential),
+}
+
+impl ItemData {
+    pub fn item_type(&self) -> ItemType {
+        match self {
+            ItemData::SecureNote { .. } => ItemType::SecureNote,
+            ItemData::Card(_) => ItemType::Card,
+            ItemData::Identity(_) => ItemType::Identity,
+            ItemData::SshKey(_) => ItemType::SshKey,
+            ItemData::ApiCredential(_) => ItemType::ApiCredential,
+        }
+    }
+
+    /// The secret of the entry, whose change restarts its rotation interval.
+    pub fn secret(&self) -> Option<&str> {
+        match self {
+            ItemData::SecureNote { body } => Some(body),
+            ItemData::Card(card) => Some(&card.number),
+            ItemData::Identity(_) => None,
+            ItemData::SshKey(key) => Some(&key.private_key),
+            ItemData::ApiCredential(credential) => Some(&credential.secret),
+        }
+    }
+
+    /// Cleans up the fields and rejects entries that are not valid for their type.
+    pub fn normalize(&mut self) -> Result<(), String> {
+        match self {
+            ItemData::SecureNote { body } => {
+                if body.trim().is_empty() {
+                    return Err("Note cannot be empty".into());
+                }
+
+                if body.chars().count() > MAX_NOTE_LENGTH {
+                    return Err(format!(
+                        "Note must be at most {} characters",
+                        MAX_NOTE_LENGTH
+                    ));
+                }
+            }
+            ItemData::Card(card) => card.normalize()?,
+            ItemData::Identity(identity) => identity.normalize()?,
+            ItemData::SshKey(key) => key.normalize()?,
+            ItemData::ApiCredential(credential) => credential.normalize()?,
+        }
+
+        Ok(())
+    }
+
+    pub fn encrypt(&self, user_id: &str, enc_key: &str) -> Result<String, String> {
+        let json =
+            serde_json::to_string(self).map_err(|e| format!("Failed to serialize entry: {}", e))?;
+        crypto::encrypt(&json, user_id, enc_key)
+            .map_err(|e| format!("Failed to encrypt entry: {}", e))
+    }
+
+    pub fn decrypt(encrypted: &str, enc_key: &str) -> Result<Self, String> {
+        let json = crypto::decrypt(encrypted, enc_key)
+            .map_err(|e| format!("Failed to decrypt entry: {}", e))?;
+        serde_json::from_str(&json).map_err(|e| format!("Failed to read entry: {}", e))
+    }
+}
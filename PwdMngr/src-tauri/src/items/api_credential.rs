@@ -0,0 +1,124 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+const MAX_SCOPES: usize = 100;
+
+/// An API key, personal access token or webhook secret.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiCredential {
+    /// Public part of the credential, e.g. an access key id or client id.
+    #[serde(default)]
+    pub key_id: String,
+    pub secret: String,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// Service that issued the credential.
+    #[serde(default)]
+    pub issuer: String,
+    /// Last day the credential is valid, as `YYYY-MM-DD`.
+    #[serde(default)]
+    pub expires_on: Option<NaiveDate>,
+}
+
+impl ApiCredential {
+    pub fn normalize(&mut self) -> Result<(), String> {
+        self.key_id = self.key_id.trim().to_string();
+        self.issuer = self.issuer.trim().to_string();
+
+        self.secret = self.secret.trim().to_string();
+        if self.secret.is_empty() {
+            return Err("Secret cannot be empty".into());
+        }
+
+        let mut scopes: Vec<String> = Vec::new();
+        for scope in &self.scopes {
+            let scope = scope.trim();
+            if !scope.is_empty() && !scopes.iter().any(|s| s == scope) {
+                scopes.push(scope.to_string());
+            }
+        }
+
+        if scopes.len() > MAX_SCOPES {
+            return Err(format!(
+                "A credential can have at most {} scopes",
+                MAX_SCOPES
+            ));
+        }
+        self.scopes = scopes;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn credential() -> ApiCredential {
+        ApiCredential {
+            key_id: " AKIAEXAMPLE ".into(),
+            secret: " s3cr3t ".into(),
+            scopes: Vec::new(),
+            issuer: " AWS ".into(),
+            expires_on: None,
+        }
+    }
+
+    #[test]
+    fn normalize_trims_fields() {
+        let mut credential = credential();
+
+        credential.normalize().unwrap();
+
+        assert_eq!(credential.key_id, "AKIAEXAMPLE");
+        assert_eq!(credential.secret, "s3cr3t");
+        assert_eq!(credential.issuer, "AWS");
+    }
+
+    #[test]
+    fn normalize_needs_secret() {
+        let mut credential = credential();
+        credential.secret = "   ".into();
+
+        assert_eq!(
+            credential.normalize().unwrap_err(),
+            "Secret cannot be empty"
+        );
+    }
+
+    #[test]
+    fn normalize_dedupes_and_limits_scopes() {
+        let mut credential = credential();
+        credential.scopes = vec![" repo ".into(), "".into(), "repo".into(), "read:org".into()];
+
+        credential.normalize().unwrap();
+        assert_eq!(credential.scopes, ["repo", "read:org"]);
+
+        credential.scopes = (0..=MAX_SCOPES).map(|i| format!("scope{}", i)).collect();
+        assert_eq!(
+            credential.normalize().unwrap_err(),
+            "A credential can have at most 100 scopes"
+        );
+    }
+
+    #[test]
+    fn reads_expiry_date() {
+        let mut credential: ApiCredential =
+            serde_json::from_str(r#"{"secret": "s3cr3t", "expires_on": "2026-03-31"}"#).unwrap();
+
+        credential.normalize().unwrap();
+        assert_eq!(credential.expires_on, NaiveDate::from_ymd_opt(2026, 3, 31));
+
+        let credential: ApiCredential = serde_json::from_str(r#"{"secret": "s3cr3t"}"#).unwrap();
+        assert_eq!(credential.expires_on, None);
+
+        for expires_on in ["2026-02-30", "31/03/2026", "tomorrow"] {
+            let json = format!(r#"{{"secret": "s3cr3t", "expires_on": "{}"}}"#, expires_on);
+            assert!(
+                serde_json::from_str::<ApiCredential>(&json).is_err(),
+                "{}",
+                expires_on
+            );
+        }
+    }
+}
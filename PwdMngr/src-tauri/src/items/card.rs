@@ -0,0 +1,202 @@
+use chrono::{Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CardBrand {
+    Visa,
+    Mastercard,
+    Amex,
+    Discover,
+    DinersClub,
+    Jcb,
+    UnionPay,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Card {
+    pub cardholder: String,
+    pub number: String,
+    pub expiry_month: u32,
+    pub expiry_year: i32,
+    #[serde(default)]
+    pub cvv: String,
+    /// Detected from the number when the card is saved.
+    #[serde(default)]
+    pub brand: Option<CardBrand>,
+}
+
+impl Card {
+    pub fn normalize(&mut self) -> Result<(), String> {
+        self.cardholder = self.cardholder.trim().to_string();
+        if self.cardholder.is_empty() {
+            return Err("Cardholder cannot be empty".into());
+        }
+
+        let number: String = self
+            .number
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '-')
+            .collect();
+        if !number.chars().all(|c| c.is_ascii_digit()) || !(12..=19).contains(&number.len()) {
+            return Err("Card number must be 12 to 19 digits".into());
+        }
+        if !luhn_valid(&number) {
+            return Err("Card number is not valid".into());
+        }
+        self.number = number;
+
+        let brand = detect_brand(&self.number);
+        self.brand = Some(brand);
+
+        if !(1..=12).contains(&self.expiry_month) {
+            return Err("Expiry month must be between 1 and 12".into());
+        }
+        if self.expiry_year < 100 {
+            self.expiry_year += 2000;
+        }
+        if !(2000..=2100).contains(&self.expiry_year) {
+            return Err("Expiry year is not valid".into());
+        }
+
+        self.cvv = self.cvv.trim().to_string();
+        let cvv_length = if brand == CardBrand::Amex { 4 } else { 3 };
+        if !self.cvv.is_empty()
+            && (self.cvv.len() != cvv_length || !self.cvv.chars().all(|c| c.is_ascii_digit()))
+        {
+            return Err(format!("CVV must be {} digits", cvv_length));
+        }
+
+        Ok(())
+    }
+
+    /// Cards stay valid until the end of their expiry month.
+    pub fn expires_on(&self) -> Option<NaiveDate> {
+        let first_of_month = NaiveDate::from_ymd_opt(self.expiry_year, self.expiry_month, 1)?;
+        first_of_month
+            .checked_add_months(Months::new(1))?
+            .pred_opt()
+    }
+
+    pub fn last_four(&self) -> &str {
+        &self.number[self.number.len().saturating_sub(4)..]
+    }
+}
+
+pub fn luhn_valid(number: &str) -> bool {
+    let mut sum = 0;
+
+    for (i, c) in number.chars().rev().enumerate() {
+        let Some(mut digit) = c.to_digit(10) else {
+            return false;
+        };
+        if i % 2 == 1 {
+            digit *= 2;
+            if digit > 9 {
+                digit -= 9;
+            }
+        }
+        sum += digit;
+    }
+
+    sum % 10 == 0
+}
+
+pub fn detect_brand(number: &str) -> CardBrand {
+    let prefix = |len: usize| {
+        number
+            .get(..len)
+            .and_then(|p| p.parse::<u32>().ok())
+            .unwrap_or(0)
+    };
+
+    match (prefix(1), prefix(2), prefix(3), prefix(4)) {
+        (4, _, _, _) => CardBrand::Visa,
+        (_, 34 | 37, _, _) => CardBrand::Amex,
+        (_, 51..=55, _, _) | (_, _, _, 2221..=2720) => CardBrand::Mastercard,
+        (_, 65, _, _) | (_, _, 644..=649, _) | (_, _, _, 6011) => CardBrand::Discover,
+        (_, 36 | 38 | 39, _, _) | (_, _, 300..=305, _) => CardBrand::DinersClub,
+        (_, _, _, 3528..=3589) => CardBrand::Jcb,
+        (_, 62, _, _) => CardBrand::UnionPay,
+        _ => CardBrand::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn luhn_accepts_valid_numbers() {
+        for number in [
+            "4111111111111111",
+            "5555555555554444",
+            "378282246310005",
+            "6011111111111117",
+            "79927398713",
+        ] {
+            assert!(luhn_valid(number), "{}", number);
+        }
+    }
+
+    #[test]
+    fn luhn_rejects_invalid_numbers() {
+        assert!(!luhn_valid("4111111111111112"));
+        assert!(!luhn_valid("79927398710"));
+        assert!(!luhn_valid("4111 1111 1111 1111"));
+    }
+
+    #[test]
+    fn detects_brands() {
+        for (number, brand) in [
+            ("4111111111111111", CardBrand::Visa),
+            ("5555555555554444", CardBrand::Mastercard),
+            ("2221000000000009", CardBrand::Mastercard),
+            ("378282246310005", CardBrand::Amex),
+            ("6011111111111117", CardBrand::Discover),
+            ("6500000000000002", CardBrand::Discover),
+            ("30569309025904", CardBrand::DinersClub),
+            ("3530111333300000", CardBrand::Jcb),
+            ("6200000000000005", CardBrand::UnionPay),
+            ("9999999999999995", CardBrand::Unknown),
+        ] {
+            assert_eq!(detect_brand(number), brand, "{}", number);
+        }
+    }
+
+    #[test]
+    fn normalize_cleans_up_card() {
+        let mut card = Card {
+            cardholder: " Jane Doe ".into(),
+            number: "3782 822463-10005".into(),
+            expiry_month: 2,
+            expiry_year: 28,
+            cvv: "1234".into(),
+            brand: None,
+        };
+
+        card.normalize().unwrap();
+
+        assert_eq!(card.cardholder, "Jane Doe");
+        assert_eq!(card.number, "378282246310005");
+        assert_eq!(card.brand, Some(CardBrand::Amex));
+        assert_eq!(card.expiry_year, 2028);
+        assert_eq!(card.last_four(), "0005");
+        assert_eq!(card.expires_on(), NaiveDate::from_ymd_opt(2028, 2, 29));
+    }
+
+    #[test]
+    fn normalize_checks_cvv_length_for_brand() {
+        let mut card = Card {
+            cardholder: "Jane Doe".into(),
+            number: "4111111111111111".into(),
+            expiry_month: 12,
+            expiry_year: 2030,
+            cvv: "1234".into(),
+            brand: None,
+        };
+
+        assert_eq!(card.normalize().unwrap_err(), "CVV must be 3 digits");
+    }
+}
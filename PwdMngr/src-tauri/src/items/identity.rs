@@ -0,0 +1,258 @@
+use serde::{Deserialize, Serialize};
+
+const MAX_ADDRESSES: usize = 10;
+const MAX_DOCUMENTS: usize = 10;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Address {
+    /// e.g. "Home" or "Office".
+    pub label: String,
+    pub street: String,
+    pub city: String,
+    pub region: String,
+    pub postal_code: String,
+    pub country: String,
+}
+
+impl Address {
+    fn is_empty(&self) -> bool {
+        [
+            &self.street,
+            &self.city,
+            &self.region,
+            &self.postal_code,
+            &self.country,
+        ]
+        .iter()
+        .all(|part| part.is_empty())
+    }
+}
+
+/// A tax id or passport number, with the country that issued it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IdentityDocument {
+    pub number: String,
+    pub country: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Identity {
+    pub first_name: String,
+    pub middle_name: String,
+    pub last_name: String,
+    pub addresses: Vec<Address>,
+    pub phone: String,
+    pub email: String,
+    pub company: String,
+    pub tax_ids: Vec<IdentityDocument>,
+    pub passport_numbers: Vec<IdentityDocument>,
+}
+
+impl Identity {
+    pub fn normalize(&mut self) -> Result<(), String> {
+        for value in [
+            &mut self.first_name,
+            &mut self.middle_name,
+            &mut self.last_name,
+            &mut self.phone,
+            &mut self.email,
+            &mut self.company,
+        ] {
+            *value = value.trim().to_string();
+        }
+
+        if self.first_name.is_empty() && self.last_name.is_empty() && self.company.is_empty() {
+            return Err("An identity needs a name or a company".into());
+        }
+
+        if !self.email.is_empty() && !is_plausible_email(&self.email) {
+            return Err("Email address is not valid".into());
+        }
+
+        if !self.phone.is_empty()
+            && !self
+                .phone
+                .chars()
+                .all(|c| c.is_ascii_digit() || " +-().".contains(c))
+        {
+            return Err("Phone number may only contain digits, spaces and + - ( ) .".into());
+        }
+
+        for address in &mut self.addresses {
+            for part in [
+                &mut address.label,
+                &mut address.street,
+                &mut address.city,
+                &mut address.region,
+                &mut address.postal_code,
+                &mut address.country,
+            ] {
+                *part = part.trim().to_string();
+            }
+        }
+        self.addresses.retain(|address| !address.is_empty());
+
+        if self.addresses.len() > MAX_ADDRESSES {
+            return Err(format!(
+                "An identity can have at most {} addresses",
+                MAX_ADDRESSES
+            ));
+        }
+
+        for documents in [&mut self.tax_ids, &mut self.passport_numbers] {
+            for document in documents.iter_mut() {
+                document.number = document.number.trim().to_string();
+                document.country = document.country.trim().to_string();
+            }
+            documents.retain(|document| !document.number.is_empty());
+
+            if documents.len() > MAX_DOCUMENTS {
+                return Err(format!(
+                    "An identity can have at most {} tax ids and {} passport numbers",
+                    MAX_DOCUMENTS, MAX_DOCUMENTS
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn is_plausible_email(email: &str) -> bool {
+    match email.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !email.chars().any(char::is_whitespace)
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jane() -> Identity {
+        Identity {
+            first_name: " Jane ".into(),
+            last_name: "Doe".into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn normalize_trims_and_drops_empty_entries() {
+        let mut identity = jane();
+        identity.email = " jane@example.com ".into();
+        identity.phone = "+1 (555) 010-0000".into();
+        identity.addresses = vec![
+            Address {
+                label: " Home ".into(),
+                city: " Springfield ".into(),
+                ..Default::default()
+            },
+            Address {
+                label: "Empty".into(),
+                ..Default::default()
+            },
+        ];
+        identity.tax_ids = vec![
+            IdentityDocument {
+                number: " 123-45-6789 ".into(),
+                country: " US ".into(),
+            },
+            IdentityDocument {
+                number: "  ".into(),
+                country: "DE".into(),
+            },
+        ];
+
+        identity.normalize().unwrap();
+
+        assert_eq!(identity.first_name, "Jane");
+        assert_eq!(identity.email, "jane@example.com");
+        assert_eq!(identity.addresses.len(), 1);
+        assert_eq!(identity.addresses[0].label, "Home");
+        assert_eq!(identity.addresses[0].city, "Springfield");
+        assert_eq!(identity.tax_ids.len(), 1);
+        assert_eq!(identity.tax_ids[0].number, "123-45-6789");
+        assert_eq!(identity.tax_ids[0].country, "US");
+    }
+
+    #[test]
+    fn normalize_needs_name_or_company() {
+        let mut identity = Identity {
+            first_name: "  ".into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            identity.normalize().unwrap_err(),
+            "An identity needs a name or a company"
+        );
+
+        identity.company = "Acme".into();
+        assert!(identity.normalize().is_ok());
+    }
+
+    #[test]
+    fn normalize_checks_email_and_phone() {
+        for email in [
+            "jane",
+            "@example.com",
+            "jane@example",
+            "jane@.com",
+            "ja ne@example.com",
+        ] {
+            let mut identity = jane();
+            identity.email = email.into();
+            assert_eq!(
+                identity.normalize().unwrap_err(),
+                "Email address is not valid",
+                "{}",
+                email
+            );
+        }
+
+        let mut identity = jane();
+        identity.phone = "555-CALL-NOW".into();
+        assert_eq!(
+            identity.normalize().unwrap_err(),
+            "Phone number may only contain digits, spaces and + - ( ) ."
+        );
+    }
+
+    #[test]
+    fn normalize_limits_addresses_and_documents() {
+        let mut identity = jane();
+        identity.addresses = vec![
+            Address {
+                street: "Main St".into(),
+                ..Default::default()
+            };
+            MAX_ADDRESSES + 1
+        ];
+        assert_eq!(
+            identity.normalize().unwrap_err(),
+            "An identity can have at most 10 addresses"
+        );
+
+        let mut identity = jane();
+        identity.passport_numbers = vec![
+            IdentityDocument {
+                number: "X1234567".into(),
+                country: "FR".into(),
+            };
+            MAX_DOCUMENTS + 1
+        ];
+        assert_eq!(
+            identity.normalize().unwrap_err(),
+            "An identity can have at most 10 tax ids and 10 passport numbers"
+        );
+    }
+}
@@ -1,23 +1,86 @@
+pub mod attachments;
+pub mod audit;
 pub mod commands;
+pub mod config;
 pub mod crypto;
+pub mod custom_fields;
+pub mod data_dir;
 pub mod db;
+pub mod entry_urls;
+pub mod error;
+pub mod items;
 pub mod models;
-pub mod user_state;
+pub mod otp;
+pub mod password_policy;
+pub mod revisions;
+pub mod rotation;
+pub mod session;
+pub mod trash;
+pub mod vault;
 
 use commands::{
-    delete_password, get_password_details, get_passwords, login_user, logout_user, new_password,
-    register_user, update_password, search_passwords, get_all_passwords_for_export, prepare_passwords_for_export, import_passwords_from_data
+    delete_password, toggle_favorite, get_password_details, get_passwords, get_security_events, get_session, login_user, logout_user, lock_vault, unlock_vault, new_password,
+    register_user, update_password, verify_master_password, change_master_password, delete_account, rename_user, get_profile, update_profile, search_passwords, get_passwords_for_url, get_all_passwords_for_export, prepare_passwords_for_export, import_passwords_from_data
 };
 
+use commands::attachments::{
+    add_attachment, delete_attachment, download_attachment, list_attachments,
+};
+use commands::folders::{
+    create_folder, delete_folder, list_folders, move_folder, rename_folder, set_password_folder,
+};
+use commands::history::{get_password_history, reveal_previous_password};
+use commands::items::{
+    get_expiring_api_credentials, get_expiring_cards, get_identity, new_identity, new_item,
+    update_identity, update_item,
+};
+use commands::revisions::{diff_revisions, list_revisions, restore_revision};
+use commands::rotation::{get_rotation_due, set_entry_rotation};
+use commands::ssh_keys::{export_ssh_public_key, generate_ssh_key, import_ssh_key};
+use commands::tags::{
+    delete_tag, list_tags, merge_tags, rename_tag, tag_password, untag_password,
+};
+use commands::totp::{get_totp_code, set_totp};
+use commands::trash::{empty_trash, list_trash, restore_entry};
+use commands::vaults::{close_vault, create_vault, list_vaults, open_vault, switch_vault};
+
+use data_dir::DataDir;
+use session::Session;
 use sqlx::SqlitePool;
-use std::sync::{Arc, Mutex};
-use tauri::{async_runtime, generate_handler, Manager};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use tauri::{async_runtime, generate_handler, Manager, WebviewWindowBuilder};
+use vault::Vaults;
 
 #[derive(Clone)]
-pub struct DatabasePool(Arc<SqlitePool>);
+pub struct DatabasePool(Arc<RwLock<Vaults>>);
+
+impl DatabasePool {
+    /// Returns the active vault, which new sessions log into, and its pool.
+    pub fn active(&self) -> Result<(PathBuf, Arc<SqlitePool>), String> {
+        let vaults = self.0.read().unwrap();
+        vaults
+            .active
+            .as_ref()
+            .and_then(|path| Some((path.clone(), vaults.open.get(path)?.clone())))
+            .ok_or_else(|| "No vault is open".to_string())
+    }
+
+    /// Returns the pool of the vault a session logged into.
+    pub fn for_session(&self, session: &Session) -> Result<Arc<SqlitePool>, String> {
+        self.0
+            .read()
+            .unwrap()
+            .open
+            .get(&session.vault_path)
+            .cloned()
+            .ok_or_else(|| "Vault is not open".to_string())
+    }
+}
 
 #[derive(Default, Clone)]
-pub struct UserState(pub Arc<Mutex<Option<String>>>);
+pub struct SessionRegistry(pub Arc<Mutex<HashMap<String, Session>>>);
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -30,14 +93,57 @@ pub fn run() {
         .setup(|app| {
             let app_handle = app.handle();
 
+            let data_dir = DataDir::resolve(app_handle)?;
+
+            let vault_path = db::default_vault_path(&data_dir.path);
             let pool = async_runtime::block_on(async {
-                db::establish_connection(&app_handle)
+                db::connect(&vault_path, true)
                     .await
                     .expect("Failed to establish database connection")
             });
 
-            app.manage(DatabasePool(Arc::new(pool)));
-            app.manage(UserState::default());
+            let pool = DatabasePool(Arc::new(RwLock::new(Vaults::with_active(vault_path, pool))));
+            let sessions = SessionRegistry::default();
+            let config = config::load_config(&data_dir.path);
+
+            if config.auto_lock_minutes > 0 {
+                async_runtime::spawn(session::run_auto_lock(
+                    sessions.clone(),
+                    pool.clone(),
+                    config.auto_lock_minutes,
+                ));
+            }
+
+            if config.session_lifetime_hours > 0 {
+                async_runtime::spawn(session::run_expiry(
+                    sessions.clone(),
+                    pool.clone(),
+                    config.session_lifetime_hours,
+                ));
+            }
+
+            if config.trash_retention_days > 0 {
+                async_runtime::spawn(trash::run_purge(
+                    pool.clone(),
+                    config.trash_retention_days,
+                ));
+            }
+
+            app.manage(pool);
+            app.manage(sessions);
+            app.manage(config);
+
+            // Created here rather than from tauri.conf.json so the webview profile
+            // can be kept inside the data directory in portable mode.
+            if let Some(window_config) = app.config().app.windows.first().cloned() {
+                let mut window = WebviewWindowBuilder::from_config(app_handle, &window_config)?;
+                if data_dir.portable {
+                    window = window.data_directory(data_dir.path.join("webview"));
+                }
+                window.build()?;
+            }
+
+            app.manage(data_dir);
 
             Ok(())
         })
@@ -45,15 +151,70 @@ pub fn run() {
             register_user,
             login_user,
             logout_user,
+            lock_vault,
+            unlock_vault,
+            get_session,
+            get_security_events,
+            verify_master_password,
+            change_master_password,
+            delete_account,
+            rename_user,
+            get_profile,
+            update_profile,
             new_password,
+            new_item,
+            update_item,
+            get_expiring_cards,
+            get_expiring_api_credentials,
+            add_attachment,
+            list_attachments,
+            download_attachment,
+            delete_attachment,
+            new_identity,
+            update_identity,
+            get_identity,
+            import_ssh_key,
+            generate_ssh_key,
+            export_ssh_public_key,
+            set_totp,
+            get_totp_code,
             get_passwords,
             get_password_details,
             update_password,
             delete_password,
+            toggle_favorite,
+            list_trash,
+            restore_entry,
+            empty_trash,
+            get_password_history,
+            reveal_previous_password,
+            set_entry_rotation,
+            get_rotation_due,
+            list_revisions,
+            diff_revisions,
+            restore_revision,
             search_passwords,
+            get_passwords_for_url,
             get_all_passwords_for_export,
             prepare_passwords_for_export,
-            import_passwords_from_data
+            import_passwords_from_data,
+            list_folders,
+            create_folder,
+            rename_folder,
+            move_folder,
+            delete_folder,
+            set_password_folder,
+            list_tags,
+            tag_password,
+            untag_password,
+            rename_tag,
+            merge_tags,
+            delete_tag,
+            list_vaults,
+            create_vault,
+            open_vault,
+            switch_vault,
+            close_vault
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -8,6 +8,9 @@ pub struct User {
     pub id: String,
     pub username: String,
     pub password_hash: String,
+    pub display_name: Option<String>,
+    pub email_hint: Option<String>,
+    pub password_hint: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -18,21 +21,174 @@ pub struct Password {
     pub id: String,
     pub user_id: String,
     pub website: String,
-    pub website_url: Option<String>,
     pub encrypted_username: String,
     pub encrypted_password: String,
     pub notes: Option<String>,
+    pub item_type: ItemType,
+    pub encrypted_data: Option<String>,
+    pub folder_id: Option<String>,
+    pub favorite: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum ItemType {
+    Login,
+    SecureNote,
+    Card,
+    Identity,
+    SshKey,
+    ApiCredential,
+}
+
+impl ItemType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ItemType::Login => "login",
+            ItemType::SecureNote => "secure_note",
+            ItemType::Card => "card",
+            ItemType::Identity => "identity",
+            ItemType::SshKey => "ssh_key",
+            ItemType::ApiCredential => "api_credential",
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct PasswordRecord {
     pub id: String,
     pub website: String,
-    pub website_url: Option<String>,
     pub encrypted_username: String,
     pub encrypted_password: String,
     pub notes: Option<String>,
+    pub item_type: ItemType,
+    pub encrypted_data: Option<String>,
+    pub encrypted_totp: Option<String>,
+    pub folder_id: Option<String>,
+    pub favorite: bool,
     pub updated_at: DateTime<Utc>,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum CustomFieldType {
+    Text,
+    Hidden,
+    Boolean,
+    Url,
+}
+
+/// A custom field as sent to and from the frontend, with its value in plaintext.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub field_type: CustomFieldType,
+    pub value: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct CustomFieldRecord {
+    pub id: String,
+    pub password_id: String,
+    pub position: i64,
+    pub name: String,
+    pub field_type: CustomFieldType,
+    pub encrypted_value: String,
+}
+
+/// How a URL of an entry is compared with the address of a page.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum UrlMatch {
+    /// Same registrable domain, e.g. `login.example.co.uk` matches `example.co.uk`.
+    #[default]
+    BaseDomain,
+    /// Same host and port.
+    Host,
+    StartsWith,
+    Exact,
+    Regex,
+    /// Kept on the entry but never offered for a page.
+    Never,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryUrl {
+    pub url: String,
+    #[serde(default)]
+    pub match_mode: UrlMatch,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct EntryUrlRecord {
+    pub password_id: String,
+    pub url: String,
+    pub match_mode: UrlMatch,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct AttachmentRecord {
+    pub id: String,
+    pub password_id: String,
+    pub encrypted_name: String,
+    pub size: i64,
+    pub chunk_count: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Folder {
+    pub id: String,
+    pub parent_id: Option<String>,
+    pub name: String,
+    pub password_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Tag {
+    pub id: String,
+    pub name: String,
+    pub password_count: i64,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TagMatch {
+    /// Entries carrying every listed tag.
+    All,
+    /// Entries carrying at least one listed tag.
+    #[default]
+    Any,
+}
+
+/// Narrows `get_passwords` and `search_passwords` to part of the vault and
+/// controls whether favorites are listed ahead of other entries.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EntryFilter {
+    pub folder_id: Option<String>,
+    pub include_subfolders: bool,
+    /// Tag ids; an empty list does not filter by tag.
+    pub tags: Vec<String>,
+    pub tag_match: TagMatch,
+    pub item_type: Option<ItemType>,
+    pub favorites_first: bool,
+    pub favorites_only: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct SecurityEvent {
+    pub id: String,
+    pub event: String,
+    pub source: Option<String>,
+    pub details: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,397 @@
+use crate::crypto;
+use data_encoding::BASE32_NOPAD;
+use percent_encoding::percent_decode_str;
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+const STEAM_ALPHABET: &[u8] = b"23456789BCDFGHJKMNPQRTVWXY";
+const STEAM_DIGITS: u32 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OtpKind {
+    Totp,
+    Hotp,
+    /// Steam Guard codes: TOTP with five characters from a custom alphabet.
+    Steam,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OtpAlgorithm {
+    #[default]
+    #[serde(rename = "SHA1")]
+    Sha1,
+    #[serde(rename = "SHA256")]
+    Sha256,
+    #[serde(rename = "SHA512")]
+    Sha512,
+}
+
+impl OtpAlgorithm {
+    fn as_str(self) -> &'static str {
+        match self {
+            OtpAlgorithm::Sha1 => "SHA1",
+            OtpAlgorithm::Sha256 => "SHA256",
+            OtpAlgorithm::Sha512 => "SHA512",
+        }
+    }
+
+    fn hmac(self) -> hmac::Algorithm {
+        match self {
+            OtpAlgorithm::Sha1 => hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY,
+            OtpAlgorithm::Sha256 => hmac::HMAC_SHA256,
+            OtpAlgorithm::Sha512 => hmac::HMAC_SHA512,
+        }
+    }
+}
+
+/// A TOTP or HOTP secret with its settings, stored encrypted as JSON in
+/// `passwords.encrypted_totp`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Otp {
+    pub kind: OtpKind,
+    /// Base32 without padding.
+    pub secret: String,
+    pub algorithm: OtpAlgorithm,
+    pub digits: u32,
+    pub period: u64,
+    /// Next counter value of an HOTP secret.
+    pub counter: u64,
+    pub issuer: String,
+    pub account: String,
+}
+
+impl Otp {
+    /// Reads an `otpauth://totp/...` or `otpauth://hotp/...` URI, a `steam://SECRET`
+    /// URI or a bare base32 secret, which is treated as a default TOTP secret.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+
+        if let Some(secret) = input.strip_prefix("steam://") {
+            return Self::new(OtpKind::Steam, secret).and_then(Self::validated);
+        }
+
+        if !input.contains("://") {
+            return Self::new(OtpKind::Totp, input).and_then(Self::validated);
+        }
+
+        let url = Url::parse(input).map_err(|_| "Not a valid otpauth URI".to_string())?;
+        if url.scheme() != "otpauth" {
+            return Err("Not a valid otpauth URI".into());
+        }
+
+        let kind = match url.host_str().map(str::to_ascii_lowercase).as_deref() {
+            Some("totp") => OtpKind::Totp,
+            Some("hotp") => OtpKind::Hotp,
+            _ => return Err("Only totp and hotp URIs are supported".into()),
+        };
+
+        let query = |name: &str| {
+            url.query_pairs()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.trim().to_string())
+        };
+
+        let mut otp = Self::new(kind, &query("secret").ok_or("The URI has no secret")?)?;
+
+        let label = percent_decode_str(url.path().trim_start_matches('/'))
+            .decode_utf8_lossy()
+            .to_string();
+        match label.split_once(':') {
+            Some((issuer, account)) => {
+                otp.issuer = issuer.trim().to_string();
+                otp.account = account.trim().to_string();
+            }
+            None => otp.account = label.trim().to_string(),
+        }
+        if let Some(issuer) = query("issuer").filter(|issuer| !issuer.is_empty()) {
+            otp.issuer = issuer;
+        }
+
+        if let Some(algorithm) = query("algorithm") {
+            otp.algorithm = match algorithm.to_ascii_uppercase().as_str() {
+                "SHA1" => OtpAlgorithm::Sha1,
+                "SHA256" => OtpAlgorithm::Sha256,
+                "SHA512" => OtpAlgorithm::Sha512,
+                _ => return Err(format!("Unsupported algorithm {}", algorithm)),
+            };
+        }
+        if let Some(digits) = query("digits") {
+            otp.digits = digits.parse().map_err(|_| "Digits must be a number")?;
+        }
+        if let Some(period) = query("period") {
+            otp.period = period.parse().map_err(|_| "Period must be a number")?;
+        }
+        if let Some(counter) = query("counter") {
+            otp.counter = counter.parse().map_err(|_| "Counter must be a number")?;
+        }
+
+        // Some apps mark Steam secrets with an extra parameter instead of steam://
+        if query("encoder").is_some_and(|encoder| encoder.eq_ignore_ascii_case("steam")) {
+            otp.kind = OtpKind::Steam;
+            otp.digits = STEAM_DIGITS;
+        }
+
+        otp.validated()
+    }
+
+    fn new(kind: OtpKind, secret: &str) -> Result<Self, String> {
+        let secret: String = secret
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '-' && *c != '=')
+            .collect::<String>()
+            .to_ascii_uppercase();
+
+        Ok(Self {
+            kind,
+            secret,
+            algorithm: OtpAlgorithm::Sha1,
+            digits: if kind == OtpKind::Steam {
+                STEAM_DIGITS
+            } else {
+                6
+            },
+            period: 30,
+            counter: 0,
+            issuer: if kind == OtpKind::Steam {
+                "Steam".into()
+            } else {
+                String::new()
+            },
+            account: String::new(),
+        })
+    }
+
+    fn validated(self) -> Result<Self, String> {
+        if self.secret.is_empty() {
+            return Err("Secret cannot be empty".into());
+        }
+        self.key()?;
+
+        if !(4..=10).contains(&self.digits) {
+            return Err("Codes must be 4 to 10 digits".into());
+        }
+
+        if !(1..=300).contains(&self.period) {
+            return Err("Period must be between 1 and 300 seconds".into());
+        }
+
+        Ok(self)
+    }
+
+    fn key(&self) -> Result<Vec<u8>, String> {
+        BASE32_NOPAD
+            .decode(self.secret.as_bytes())
+            .map_err(|_| "Secret is not valid base32".to_string())
+    }
+
+    /// Writes the secret back out as a URI that `parse` reads.
+    pub fn to_uri(&self) -> String {
+        if self.kind == OtpKind::Steam {
+            return format!("steam://{}", self.secret);
+        }
+
+        let kind = if self.kind == OtpKind::Hotp {
+            "hotp"
+        } else {
+            "totp"
+        };
+        let label = if self.issuer.is_empty() {
+            self.account.clone()
+        } else {
+            format!("{}:{}", self.issuer, self.account)
+        };
+
+        let mut url = Url::parse(&format!("otpauth://{}/", kind)).expect("valid base URI");
+        url.set_path(&label);
+        {
+            let mut query = url.query_pairs_mut();
+            query.append_pair("secret", &self.secret);
+            if !self.issuer.is_empty() {
+                query.append_pair("issuer", &self.issuer);
+            }
+            query.append_pair("algorithm", self.algorithm.as_str());
+            query.append_pair("digits", &self.digits.to_string());
+            if self.kind == OtpKind::Hotp {
+                query.append_pair("counter", &self.counter.to_string());
+            } else {
+                query.append_pair("period", &self.period.to_string());
+            }
+        }
+
+        url.to_string()
+    }
+
+    pub fn encrypt(&self, user_id: &str, enc_key: &str) -> Result<String, String> {
+        let json = serde_json::to_string(self)
+            .map_err(|e| format!("Failed to serialize one-time password: {}", e))?;
+        crypto::encrypt(&json, user_id, enc_key)
+            .map_err(|e| format!("Failed to encrypt one-time password: {}", e))
+    }
+
+    pub fn decrypt(encrypted: &str, enc_key: &str) -> Result<Self, String> {
+        let json = crypto::decrypt(encrypted, enc_key)
+            .map_err(|e| format!("Failed to decrypt one-time password: {}", e))?;
+        serde_json::from_str(&json).map_err(|e| format!("Failed to read one-time password: {}", e))
+    }
+
+    /// The code for the time step containing `timestamp`, or for the current
+    /// counter of an HOTP secret.
+    pub fn code_at(&self, timestamp: i64) -> Result<String, String> {
+        let counter = match self.kind {
+            OtpKind::Hotp => self.counter,
+            _ => timestamp.max(0) as u64 / self.period,
+        };
+
+        let key = hmac::Key::new(self.algorithm.hmac(), &self.key()?);
+        let digest = hmac::sign(&key, &counter.to_be_bytes());
+        let digest = digest.as_ref();
+
+        // Dynamic truncation from RFC 4226
+        let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+        let value = u32::from_be_bytes([
+            digest[offset] & 0x7f,
+            digest[offset + 1],
+            digest[offset + 2],
+            digest[offset + 3],
+        ]);
+
+        if self.kind == OtpKind::Steam {
+            let mut value = value as usize;
+            let mut code = String::with_capacity(STEAM_DIGITS as usize);
+            for _ in 0..STEAM_DIGITS {
+                code.push(STEAM_ALPHABET[value % STEAM_ALPHABET.len()] as char);
+                value /= STEAM_ALPHABET.len();
+            }
+            return Ok(code);
+        }
+
+        let code = value as u64 % 10u64.pow(self.digits);
+        Ok(format!("{:0width$}", code, width = self.digits as usize))
+    }
+
+    /// Seconds until the code for `timestamp` changes; None for HOTP secrets.
+    pub fn remaining_seconds(&self, timestamp: i64) -> Option<u64> {
+        (self.kind != OtpKind::Hotp).then(|| self.period - timestamp.max(0) as u64 % self.period)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 6238 test secrets: the ASCII digits "1234567890" repeated to the key size
+    fn rfc_secret(len: usize) -> String {
+        let key: Vec<u8> = b"1234567890".iter().cycle().take(len).copied().collect();
+        BASE32_NOPAD.encode(&key)
+    }
+
+    fn rfc_totp(len: usize, algorithm: &str) -> Otp {
+        Otp::parse(&format!(
+            "otpauth://totp/ACME:bob?secret={}&digits=8&algorithm={}",
+            rfc_secret(len),
+            algorithm
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn totp_matches_rfc_6238_vectors() {
+        let sha1 = rfc_totp(20, "SHA1");
+        let sha256 = rfc_totp(32, "SHA256");
+        let sha512 = rfc_totp(64, "sha512");
+
+        for (timestamp, expected_sha1, expected_sha256, expected_sha512) in [
+            (59, "94287082", "46119246", "90693936"),
+            (1111111109, "07081804", "68084774", "25091201"),
+            (20000000000, "65353130", "77737706", "47863826"),
+        ] {
+            assert_eq!(sha1.code_at(timestamp).unwrap(), expected_sha1);
+            assert_eq!(sha256.code_at(timestamp).unwrap(), expected_sha256);
+            assert_eq!(sha512.code_at(timestamp).unwrap(), expected_sha512);
+        }
+    }
+
+    #[test]
+    fn hotp_matches_rfc_4226_vectors() {
+        let mut otp = Otp::parse(&format!(
+            "otpauth://hotp/bob?secret={}&counter=0",
+            rfc_secret(20)
+        ))
+        .unwrap();
+
+        for expected in ["755224", "287082", "359152", "969429", "338314"] {
+            assert_eq!(otp.code_at(0).unwrap(), expected);
+            otp.counter += 1;
+        }
+        assert_eq!(otp.remaining_seconds(5), None);
+    }
+
+    #[test]
+    fn uri_round_trips() {
+        let otp = Otp::parse(&format!(
+            "otpauth://totp/ACME%20Co:john%40example.com?secret={}&issuer=ACME%20Co&period=60&algorithm=SHA256",
+            rfc_secret(20).to_lowercase()
+        ))
+        .unwrap();
+        assert_eq!(otp.issuer, "ACME Co");
+        assert_eq!(otp.account, "john@example.com");
+        assert_eq!(otp.remaining_seconds(61), Some(59));
+
+        let parsed = Otp::parse(&otp.to_uri()).unwrap();
+        assert_eq!(parsed.kind, OtpKind::Totp);
+        assert_eq!(parsed.secret, otp.secret);
+        assert_eq!(parsed.algorithm, OtpAlgorithm::Sha256);
+        assert_eq!(parsed.issuer, "ACME Co");
+        assert_eq!(parsed.account, "john@example.com");
+        assert_eq!(parsed.period, 60);
+        assert_eq!(parsed.code_at(99).unwrap(), otp.code_at(99).unwrap());
+
+        let mut hotp = Otp::parse(&format!("otpauth://hotp/bob?secret={}", otp.secret)).unwrap();
+        hotp.counter = 7;
+        let parsed = Otp::parse(&hotp.to_uri()).unwrap();
+        assert_eq!(parsed.kind, OtpKind::Hotp);
+        assert_eq!(parsed.counter, 7);
+    }
+
+    #[test]
+    fn steam_codes_use_the_steam_alphabet() {
+        let steam = Otp::parse(&format!("steam://{}", rfc_secret(20))).unwrap();
+        let code = steam.code_at(59).unwrap();
+
+        assert_eq!(code.len(), STEAM_DIGITS as usize);
+        assert!(code.bytes().all(|c| STEAM_ALPHABET.contains(&c)));
+        assert_eq!(
+            Otp::parse(&steam.to_uri()).unwrap().code_at(59).unwrap(),
+            code
+        );
+
+        let encoded = Otp::parse(&format!(
+            "otpauth://totp/Steam:bob?secret={}&encoder=steam",
+            rfc_secret(20)
+        ))
+        .unwrap();
+        assert_eq!(encoded.kind, OtpKind::Steam);
+        assert_eq!(encoded.code_at(59).unwrap(), code);
+    }
+
+    #[test]
+    fn accepts_bare_secrets() {
+        let otp = Otp::parse("jbsw y3dp-ehpk 3pxp").unwrap();
+        assert_eq!(otp.kind, OtpKind::Totp);
+        assert_eq!(otp.secret, "JBSWY3DPEHPK3PXP");
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        assert!(Otp::parse("").is_err());
+        assert!(Otp::parse("otpauth://totp/bob?secret=!!!").is_err());
+        assert!(Otp::parse("otpauth://totp/bob?issuer=ACME").is_err());
+        assert!(Otp::parse("otpauth://totp/bob?secret=JBSWY3DPEHPK3PXP&digits=12").is_err());
+        assert!(Otp::parse("otpauth://totp/bob?secret=JBSWY3DPEHPK3PXP&period=0").is_err());
+        assert!(Otp::parse("otpauth://totp/bob?secret=JBSWY3DPEHPK3PXP&algorithm=MD5").is_err());
+        assert!(Otp::parse("otpauth://yubikey/bob?secret=JBSWY3DPEHPK3PXP").is_err());
+        assert!(Otp::parse("https://example.com/?secret=JBSWY3DPEHPK3PXP").is_err());
+    }
+}
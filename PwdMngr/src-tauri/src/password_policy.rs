@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+
+const COMMON_PASSWORDS: &str = include_str!("../resources/common-passwords.txt");
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub min_score: u8,
+    pub reject_common: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 12,
+            min_score: 3,
+            reject_common: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyRule {
+    MinLength,
+    MinScore,
+    CommonPassword,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyViolation {
+    pub rule: PolicyRule,
+    pub message: String,
+}
+
+impl PasswordPolicy {
+    /// Returns every rule the password breaks; an empty list means it is accepted.
+    /// `user_inputs` (e.g. the username) are penalised by the strength estimator.
+    pub fn check(&self, password: &str, user_inputs: &[&str]) -> Vec<PolicyViolation> {
+        let mut violations = Vec::new();
+
+        if password.chars().count() < self.min_length {
+            violations.push(PolicyViolation {
+                rule: PolicyRule::MinLength,
+                message: format!(
+                    "Password must be at least {} characters long",
+                    self.min_length
+                ),
+            });
+        }
+
+        let score = u8::from(zxcvbn::zxcvbn(password, user_inputs).score());
+        if score < self.min_score {
+            violations.push(PolicyViolation {
+                rule: PolicyRule::MinScore,
+                message: format!(
+                    "Password is too weak (strength {} of 4, at least {} required)",
+                    score, self.min_score
+                ),
+            });
+        }
+
+        if self.reject_common && is_common_password(password) {
+            violations.push(PolicyViolation {
+                rule: PolicyRule::CommonPassword,
+                message: "Password is too common".to_string(),
+            });
+        }
+
+        violations
+    }
+}
+
+fn is_common_password(password: &str) -> bool {
+    let password = password.to_lowercase();
+    COMMON_PASSWORDS
+        .lines()
+        .any(|common| common.trim() == password)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(policy: &PasswordPolicy, password: &str) -> Vec<PolicyRule> {
+        policy
+            .check(password, &[])
+            .into_iter()
+            .map(|violation| violation.rule)
+            .collect()
+    }
+
+    #[test]
+    fn accepts_long_random_password() {
+        assert!(rules(
+            &PasswordPolicy::default(),
+            "correct horse battery staple 42"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn rejects_short_password() {
+        let policy = PasswordPolicy {
+            min_length: 12,
+            min_score: 0,
+            reject_common: false,
+        };
+
+        assert_eq!(rules(&policy, "xK#9vq!2"), vec![PolicyRule::MinLength]);
+    }
+
+    #[test]
+    fn rejects_weak_password() {
+        let policy = PasswordPolicy {
+            min_length: 0,
+            min_score: 3,
+            reject_common: false,
+        };
+
+        assert_eq!(rules(&policy, "aaaaaaaaaaaa"), vec![PolicyRule::MinScore]);
+    }
+
+    #[test]
+    fn rejects_common_password_in_any_case() {
+        let policy = PasswordPolicy {
+            min_length: 0,
+            min_score: 0,
+            reject_common: true,
+        };
+
+        assert_eq!(rules(&policy, "PASSWORD"), vec![PolicyRule::CommonPassword]);
+        assert!(rules(
+            &PasswordPolicy {
+                reject_common: false,
+                ..policy
+            },
+            "password"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn penalises_user_inputs() {
+        let policy = PasswordPolicy {
+            min_length: 0,
+            min_score: 3,
+            reject_common: false,
+        };
+
+        assert!(policy.check("zmq7Rtx2Bvwk", &[]).is_empty());
+        assert_eq!(
+            policy
+                .check("zmq7Rtx2Bvwk", &["zmq7Rtx2Bvwk"])
+                .into_iter()
+                .map(|violation| violation.rule)
+                .collect::<Vec<_>>(),
+            vec![PolicyRule::MinScore]
+        );
+    }
+}
@@ -0,0 +1,237 @@
+use crate::{
+    crypto, custom_fields, entry_urls,
+    items::ItemData,
+    models::{CustomField, EntryUrl, ItemType},
+    otp::Otp,
+};
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sqlx::{Sqlite, Transaction};
+use uuid::Uuid;
+
+/// Everything about an entry that is kept in its revisions, in plaintext.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub website: String,
+    pub urls: Vec<EntryUrl>,
+    pub username: String,
+    pub password: String,
+    pub notes: Option<String>,
+    pub custom_fields: Vec<CustomField>,
+    pub folder_id: Option<String>,
+    pub item_type: ItemType,
+    pub data: Option<ItemData>,
+    /// Tag names, sorted.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub totp: Option<Otp>,
+    #[serde(default)]
+    pub expires_on: Option<NaiveDate>,
+    #[serde(default)]
+    pub rotation_days: Option<i64>,
+}
+
+impl Snapshot {
+    /// The fields that can change between revisions, by name.
+    pub fn fields(&self) -> Vec<(&'static str, JsonValue)> {
+        vec![
+            ("website", serde_json::json!(self.website)),
+            ("urls", serde_json::json!(self.urls)),
+            ("username", serde_json::json!(self.username)),
+            ("password", serde_json::json!(self.password)),
+            ("notes", serde_json::json!(self.notes)),
+            ("custom_fields", serde_json::json!(self.custom_fields)),
+            ("folder_id", serde_json::json!(self.folder_id)),
+            ("data", serde_json::json!(self.data)),
+            ("tags", serde_json::json!(self.tags)),
+            // The HOTP counter moves with every code, which doesn't change the entry
+            (
+                "totp",
+                serde_json::json!(self.totp.as_ref().map(|otp| Otp {
+                    counter: 0,
+                    ..otp.clone()
+                })),
+            ),
+            ("expires_on", serde_json::json!(self.expires_on)),
+            ("rotation_days", serde_json::json!(self.rotation_days)),
+        ]
+    }
+
+    pub fn encrypt(&self, user_id: &str, enc_key: &str) -> Result<String, String> {
+        let json = serde_json::to_string(self)
+            .map_err(|e| format!("Failed to serialize revision: {}", e))?;
+        crypto::encrypt(&json, user_id, enc_key)
+            .map_err(|e| format!("Failed to encrypt revision: {}", e))
+    }
+
+    pub fn decrypt(encrypted: &str, enc_key: &str) -> Result<Self, String> {
+        let json = crypto::decrypt(encrypted, enc_key)
+            .map_err(|e| format!("Failed to decrypt revision: {}", e))?;
+        serde_json::from_str(&json).map_err(|e| format!("Failed to read revision: {}", e))
+    }
+}
+
+/// Names of the fields that differ between two snapshots.
+pub fn changed_fields(before: &Snapshot, after: &Snapshot) -> Vec<&'static str> {
+    before
+        .fields()
+        .into_iter()
+        .zip(after.fields())
+        .filter(|((_, before), (_, after))| before != after)
+        .map(|((name, _), _)| name)
+        .collect()
+}
+
+/// Reads the current state of an entry.
+pub async fn load_snapshot(
+    tx: &mut Transaction<'_, Sqlite>,
+    password_id: &str,
+    enc_key: &str,
+) -> Result<Snapshot, String> {
+    let (
+        website,
+        encrypted_username,
+        encrypted_password,
+        notes,
+        folder_id,
+        item_type,
+        encrypted_data,
+        encrypted_totp,
+        expires_on,
+        rotation_days,
+    ) = sqlx::query_as::<
+        _,
+        (
+            String,
+            String,
+            String,
+            Option<String>,
+            Option<String>,
+            ItemType,
+            Option<String>,
+            Option<String>,
+            Option<NaiveDate>,
+            Option<i64>,
+        ),
+    >(
+        "SELECT website, encrypted_username, encrypted_password, notes, folder_id, item_type, encrypted_data, encrypted_totp, expires_on, rotation_days
+         FROM passwords
+         WHERE id = ?",
+    )
+        .bind(password_id)
+        .fetch_optional(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to fetch entry: {}", e))?
+        .ok_or("Entry not found")?;
+
+    let ids = [password_id.to_string()];
+    let urls = entry_urls::load_urls(&mut **tx, &ids).await?;
+    let fields = custom_fields::load_fields(&mut **tx, &ids, enc_key).await?;
+
+    let tags = sqlx::query_scalar::<_, String>(
+        "SELECT t.name FROM password_tags pt JOIN tags t ON t.id = pt.tag_id
+         WHERE pt.password_id = ?
+         ORDER BY t.name",
+    )
+    .bind(password_id)
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(|e| format!("Failed to fetch tags: {}", e))?;
+
+    Ok(Snapshot {
+        website,
+        urls: urls.into_values().next().unwrap_or_default(),
+        username: decrypt_login_field(&encrypted_username, enc_key)?,
+        password: decrypt_login_field(&encrypted_password, enc_key)?,
+        notes,
+        custom_fields: fields.into_values().next().unwrap_or_default(),
+        folder_id,
+        item_type,
+        data: encrypted_data
+            .as_deref()
+            .map(|data| ItemData::decrypt(data, enc_key))
+            .transpose()?,
+        tags,
+        totp: encrypted_totp
+            .as_deref()
+            .map(|totp| Otp::decrypt(totp, enc_key))
+            .transpose()?,
+        expires_on,
+        rotation_days,
+    })
+}
+
+/// Stores the current state of an entry as a new revision, unless it is the same
+/// as the latest one. Called both before and after a change, so entries that
+/// predate revisions or were changed some other way still get a revision to
+/// compare with.
+pub async fn record(
+    tx: &mut Transaction<'_, Sqlite>,
+    password_id: &str,
+    user_id: &str,
+    enc_key: &str,
+) -> Result<(), String> {
+    let snapshot = load_snapshot(tx, password_id, enc_key).await?;
+
+    let latest = sqlx::query_as::<_, (i64, String)>(
+        "SELECT number, encrypted_snapshot FROM entry_revisions WHERE password_id = ? ORDER BY number DESC LIMIT 1",
+    )
+    .bind(password_id)
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|e| format!("Failed to fetch revisions: {}", e))?;
+
+    let (number, changed) = match latest {
+        Some((number, encrypted_snapshot)) => {
+            let latest = Snapshot::decrypt(&encrypted_snapshot, enc_key)?;
+            (number + 1, changed_fields(&latest, &snapshot))
+        }
+        // The first revision lists every field that has a value
+        None => (
+            1,
+            snapshot
+                .fields()
+                .into_iter()
+                .filter(|(_, value)| !is_empty(value))
+                .map(|(name, _)| name)
+                .collect(),
+        ),
+    };
+
+    if number > 1 && changed.is_empty() {
+        return Ok(());
+    }
+
+    sqlx::query("INSERT INTO entry_revisions (id, password_id, number, encrypted_snapshot, changed_fields, created_at) VALUES (?, ?, ?, ?, ?, ?)")
+        .bind(Uuid::new_v4().to_string())
+        .bind(password_id)
+        .bind(number)
+        .bind(snapshot.encrypt(user_id, enc_key)?)
+        .bind(serde_json::json!(changed).to_string())
+        .bind(Utc::now())
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to save revision: {}", e))?;
+
+    Ok(())
+}
+
+fn is_empty(value: &JsonValue) -> bool {
+    match value {
+        JsonValue::Null => true,
+        JsonValue::String(value) => value.is_empty(),
+        JsonValue::Array(values) => values.is_empty(),
+        _ => false,
+    }
+}
+
+/// Entries other than logins leave the login columns empty rather than encrypted.
+fn decrypt_login_field(encrypted: &str, enc_key: &str) -> Result<String, String> {
+    if encrypted.is_empty() {
+        return Ok(String::new());
+    }
+
+    crypto::decrypt(encrypted, enc_key).map_err(|e| format!("Failed to decrypt entry: {}", e))
+}
@@ -0,0 +1,333 @@
+use crate::{items::ItemData, models::ItemType};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::Serialize;
+use serde_json::json;
+use sqlx::{Sqlite, SqliteExecutor, Transaction};
+use tauri::{Emitter, Window};
+
+/// Emitted after login or unlock when entries are past their rotation date.
+pub const ROTATION_OVERDUE_EVENT: &str = "rotation-overdue";
+
+const MAX_ROTATION_DAYS: i64 = 3650;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Schedule {
+    pub expires_on: Option<NaiveDate>,
+    pub rotation_days: Option<i64>,
+    pub password_changed_at: DateTime<Utc>,
+}
+
+impl Schedule {
+    /// The earlier of the expiry date and the end of the rotation interval.
+    pub fn due_on(&self) -> Option<NaiveDate> {
+        let rotate_on = self
+            .rotation_days
+            .map(|days| (self.password_changed_at + Duration::days(days)).date_naive());
+
+        match (self.expires_on, rotate_on) {
+            (Some(expires_on), Some(rotate_on)) => Some(expires_on.min(rotate_on)),
+            (expires_on, rotate_on) => expires_on.or(rotate_on),
+        }
+    }
+
+    /// The due date, if it is no more than `within_days` after `today`. Overdue
+    /// entries are always included.
+    fn due_within(&self, today: NaiveDate, within_days: i64) -> Option<NaiveDate> {
+        let cutoff = today + Duration::days(within_days.max(0));
+        self.due_on().filter(|due_on| *due_on <= cutoff)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DueEntry {
+    pub id: String,
+    pub website: String,
+    pub item_type: ItemType,
+    #[serde(flatten)]
+    pub schedule: Schedule,
+    pub due_on: NaiveDate,
+    pub overdue: bool,
+}
+
+pub fn validate_rotation_days(rotation_days: Option<i64>) -> Result<(), String> {
+    match rotation_days {
+        Some(days) if !(1..=MAX_ROTATION_DAYS).contains(&days) => Err(format!(
+            "Rotation interval must be between 1 and {} days",
+            MAX_ROTATION_DAYS
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Restarts the rotation interval of an entry whose password was just changed.
+pub async fn mark_password_changed(
+    tx: &mut Transaction<'_, Sqlite>,
+    password_id: &str,
+) -> Result<(), String> {
+    sqlx::query("UPDATE passwords SET password_changed_at = ? WHERE id = ?")
+        .bind(Utc::now())
+        .bind(password_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("Failed to update rotation date: {}", e))?;
+
+    Ok(())
+}
+
+/// The expiry date of an entry. API credentials carry their own expiry date,
+/// which is used instead of the column so the two can't disagree.
+fn expiry_date(
+    item_type: ItemType,
+    expires_on: Option<NaiveDate>,
+    encrypted_data: Option<&str>,
+    enc_key: &str,
+) -> Result<Option<NaiveDate>, String> {
+    match (item_type, encrypted_data) {
+        (ItemType::ApiCredential, Some(encrypted_data)) => {
+            match ItemData::decrypt(encrypted_data, enc_key)? {
+                ItemData::ApiCredential(credential) => Ok(credential.expires_on),
+                _ => Ok(None),
+            }
+        }
+        _ => Ok(expires_on),
+    }
+}
+
+pub async fn load_schedule<'e, E>(
+    executor: E,
+    password_id: &str,
+    enc_key: &str,
+) -> Result<Schedule, String>
+where
+    E: SqliteExecutor<'e>,
+{
+    let (item_type, encrypted_data, expires_on, rotation_days, password_changed_at) =
+        sqlx::query_as::<
+            _,
+            (
+                ItemType,
+                Option<String>,
+                Option<NaiveDate>,
+                Option<i64>,
+                DateTime<Utc>,
+            ),
+        >(
+            "SELECT item_type, encrypted_data, expires_on, rotation_days, COALESCE(password_changed_at, created_at)
+             FROM passwords WHERE id = ?",
+        )
+        .bind(password_id)
+        .fetch_one(executor)
+        .await
+        .map_err(|e| format!("Failed to fetch rotation schedule: {}", e))?;
+
+    Ok(Schedule {
+        expires_on: expiry_date(item_type, expires_on, encrypted_data.as_deref(), enc_key)?,
+        rotation_days,
+        password_changed_at,
+    })
+}
+
+/// Entries of a user that are overdue or due within `within_days`, soonest first.
+pub async fn due_entries<'e, E>(
+    executor: E,
+    user_id: &str,
+    enc_key: &str,
+    within_days: i64,
+) -> Result<Vec<DueEntry>, String>
+where
+    E: SqliteExecutor<'e>,
+{
+    let rows = sqlx::query_as::<
+        _,
+        (
+            String,
+            String,
+            ItemType,
+            Option<String>,
+            Option<NaiveDate>,
+            Option<i64>,
+            DateTime<Utc>,
+        ),
+    >(
+        "SELECT id, website, item_type, encrypted_data, expires_on, rotation_days, COALESCE(password_changed_at, created_at)
+         FROM passwords
+         WHERE user_id = ? AND deleted_at IS NULL
+           AND (expires_on IS NOT NULL OR rotation_days IS NOT NULL OR item_type = ?)",
+    )
+    .bind(user_id)
+    .bind(ItemType::ApiCredential)
+    .fetch_all(executor)
+    .await
+    .map_err(|e| format!("Failed to fetch rotation schedules: {}", e))?;
+
+    let today = Utc::now().date_naive();
+
+    let mut entries = Vec::new();
+
+    for (id, website, item_type, encrypted_data, expires_on, rotation_days, password_changed_at) in
+        rows
+    {
+        let schedule = Schedule {
+            expires_on: expiry_date(item_type, expires_on, encrypted_data.as_deref(), enc_key)?,
+            rotation_days,
+            password_changed_at,
+        };
+        let Some(due_on) = schedule.due_within(today, within_days) else {
+            continue;
+        };
+
+        entries.push(DueEntry {
+            id,
+            website,
+            item_type,
+            schedule,
+            due_on,
+            overdue: due_on < today,
+        });
+    }
+
+    entries.sort_by_key(|entry| entry.due_on);
+    Ok(entries)
+}
+
+/// Tells the window that just logged in or unlocked about overdue entries so it
+/// can prompt for rotation.
+pub async fn notify_overdue<'e, E>(
+    window: &Window,
+    executor: E,
+    user_id: &str,
+    enc_key: &str,
+) -> Result<(), String>
+where
+    E: SqliteExecutor<'e>,
+{
+    let overdue: Vec<DueEntry> = due_entries(executor, user_id, enc_key, 0)
+        .await?
+        .into_iter()
+        .filter(|entry| entry.overdue)
+        .collect();
+
+    if !overdue.is_empty() {
+        window
+            .emit_to(
+                window.label(),
+                ROTATION_OVERDUE_EVENT,
+                json!({
+                    "entries": overdue,
+                    "count": overdue.len()
+                }),
+            )
+            .map_err(|e| format!("Failed to emit rotation event: {}", e))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::items::api_credential::ApiCredential;
+
+    const ENC_KEY: &str = "MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY";
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn schedule(expires_on: Option<NaiveDate>, rotation_days: Option<i64>) -> Schedule {
+        Schedule {
+            expires_on,
+            rotation_days,
+            password_changed_at: date(2025, 1, 10).and_hms_opt(23, 30, 0).unwrap().and_utc(),
+        }
+    }
+
+    #[test]
+    fn due_on_uses_earlier_date() {
+        assert_eq!(schedule(None, None).due_on(), None);
+        assert_eq!(schedule(None, Some(30)).due_on(), Some(date(2025, 2, 9)));
+        assert_eq!(
+            schedule(Some(date(2025, 6, 1)), None).due_on(),
+            Some(date(2025, 6, 1))
+        );
+        assert_eq!(
+            schedule(Some(date(2025, 6, 1)), Some(30)).due_on(),
+            Some(date(2025, 2, 9))
+        );
+        assert_eq!(
+            schedule(Some(date(2025, 2, 1)), Some(30)).due_on(),
+            Some(date(2025, 2, 1))
+        );
+    }
+
+    #[test]
+    fn due_within_includes_overdue_entries() {
+        let schedule = schedule(Some(date(2025, 3, 1)), None);
+
+        assert_eq!(
+            schedule.due_within(date(2025, 3, 5), 0),
+            Some(date(2025, 3, 1))
+        );
+        assert_eq!(
+            schedule.due_within(date(2025, 3, 1), 0),
+            Some(date(2025, 3, 1))
+        );
+        assert_eq!(schedule.due_within(date(2025, 2, 28), 0), None);
+        assert_eq!(
+            schedule.due_within(date(2025, 2, 22), 7),
+            Some(date(2025, 3, 1))
+        );
+        assert_eq!(schedule.due_within(date(2025, 2, 21), 7), None);
+        assert_eq!(schedule.due_within(date(2025, 2, 28), -5), None);
+    }
+
+    #[test]
+    fn validates_rotation_days() {
+        assert!(validate_rotation_days(None).is_ok());
+        assert!(validate_rotation_days(Some(1)).is_ok());
+        assert!(validate_rotation_days(Some(MAX_ROTATION_DAYS)).is_ok());
+        for days in [0, -1, MAX_ROTATION_DAYS + 1] {
+            assert_eq!(
+                validate_rotation_days(Some(days)).unwrap_err(),
+                "Rotation interval must be between 1 and 3650 days"
+            );
+        }
+    }
+
+    #[test]
+    fn api_credentials_use_their_own_expiry() {
+        let credential = ItemData::ApiCredential(ApiCredential {
+            key_id: String::new(),
+            secret: "s3cr3t".into(),
+            scopes: Vec::new(),
+            issuer: String::new(),
+            expires_on: Some(date(2025, 4, 30)),
+        })
+        .encrypt("user", ENC_KEY)
+        .unwrap();
+
+        assert_eq!(
+            expiry_date(
+                ItemType::ApiCredential,
+                Some(date(2030, 1, 1)),
+                Some(&credential),
+                ENC_KEY
+            ),
+            Ok(Some(date(2025, 4, 30)))
+        );
+
+        let note = ItemData::SecureNote { body: "b".into() }
+            .encrypt("user", ENC_KEY)
+            .unwrap();
+        assert_eq!(
+            expiry_date(
+                ItemType::SecureNote,
+                Some(date(2030, 1, 1)),
+                Some(&note),
+                ENC_KEY
+            ),
+            Ok(Some(date(2030, 1, 1)))
+        );
+        assert_eq!(expiry_date(ItemType::Login, None, None, ENC_KEY), Ok(None));
+    }
+}
@@ -0,0 +1,363 @@
+use crate::{audit, crypto, error::CommandError, DatabasePool, SessionRegistry};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionScope {
+    Full,
+    ReadOnly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionSource {
+    Window,
+    Cli,
+}
+
+impl SessionSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SessionSource::Window => "window",
+            SessionSource::Cli => "cli",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Session {
+    /// The vault the session logged into; its commands run against that vault.
+    pub vault_path: PathBuf,
+    pub user_id: String,
+    /// Empty while the session is locked.
+    pub enc_key: String,
+    pub locked: bool,
+    pub scope: SessionScope,
+    pub source: SessionSource,
+    pub previous_login_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub last_activity: DateTime<Utc>,
+    pub verified_at: DateTime<Utc>,
+}
+
+pub fn create_session(
+    state: &State<SessionRegistry>,
+    vault_path: PathBuf,
+    user_id: String,
+    enc_key: String,
+    scope: SessionScope,
+    source: SessionSource,
+    previous_login_at: Option<DateTime<Utc>>,
+) -> Result<String, String> {
+    let token =
+        crypto::generate_session_token().map_err(|e| format!("Failed to create session: {}", e))?;
+    let now = Utc::now();
+
+    state.0.lock().unwrap().insert(
+        token.clone(),
+        Session {
+            vault_path,
+            user_id,
+            enc_key,
+            locked: false,
+            scope,
+            source,
+            previous_login_at,
+            created_at: now,
+            last_activity: now,
+            verified_at: now,
+        },
+    );
+
+    Ok(token)
+}
+
+/// Looks up a session whether or not it is locked, without counting as activity.
+pub fn find_session(state: &State<SessionRegistry>, token: &str) -> Result<Session, String> {
+    match state.0.lock().unwrap().get(token) {
+        Some(session) => Ok(session.clone()),
+        None => Err("Not authenticated".to_string()),
+    }
+}
+
+pub fn require_session(state: &State<SessionRegistry>, token: &str) -> Result<Session, String> {
+    let mut sessions = state.0.lock().unwrap();
+    match sessions.get_mut(token) {
+        Some(session) if session.locked => Err("Vault is locked".to_string()),
+        Some(session) => {
+            session.last_activity = Utc::now();
+            Ok(session.clone())
+        }
+        None => Err("Not authenticated".to_string()),
+    }
+}
+
+pub fn require_writable_session(
+    state: &State<SessionRegistry>,
+    token: &str,
+) -> Result<Session, String> {
+    let session = require_session(state, token)?;
+    match session.scope {
+        SessionScope::Full => Ok(session),
+        SessionScope::ReadOnly => Err("Session is read-only".to_string()),
+    }
+}
+
+pub fn require_recent_verification(
+    session: &Session,
+    window_minutes: i64,
+) -> Result<(), CommandError> {
+    if Utc::now() - session.verified_at > Duration::minutes(window_minutes) {
+        return Err(CommandError::reauth_required(window_minutes));
+    }
+
+    Ok(())
+}
+
+pub fn mark_verified(state: &State<SessionRegistry>, token: &str) -> Result<(), String> {
+    let mut sessions = state.0.lock().unwrap();
+    match sessions.get_mut(token) {
+        Some(session) => {
+            session.verified_at = Utc::now();
+            Ok(())
+        }
+        None => Err("Not authenticated".to_string()),
+    }
+}
+
+pub fn end_session(state: &State<SessionRegistry>, token: &str) -> Option<Session> {
+    state.0.lock().unwrap().remove(token)
+}
+
+/// Signs out every session of a vault that is being closed.
+pub fn end_vault_sessions(state: &State<SessionRegistry>, vault_path: &Path) {
+    state
+        .0
+        .lock()
+        .unwrap()
+        .retain(|_, session| session.vault_path != vault_path);
+}
+
+pub fn end_user_sessions(state: &State<SessionRegistry>, user_id: &str) {
+    state
+        .0
+        .lock()
+        .unwrap()
+        .retain(|_, session| session.user_id != user_id);
+}
+
+pub fn replace_user_key(state: &State<SessionRegistry>, user_id: &str, enc_key: &str) {
+    for session in state.0.lock().unwrap().values_mut() {
+        if session.user_id == user_id && !session.locked {
+            session.enc_key = enc_key.to_string();
+        }
+    }
+}
+
+pub fn lock_session(state: &State<SessionRegistry>, token: &str) -> Result<Session, String> {
+    let mut sessions = state.0.lock().unwrap();
+    match sessions.get_mut(token) {
+        Some(session) => {
+            discard_key(session);
+            Ok(session.clone())
+        }
+        None => Err("Not authenticated".to_string()),
+    }
+}
+
+pub fn unlock_session(
+    state: &State<SessionRegistry>,
+    token: &str,
+    enc_key: String,
+) -> Result<(), String> {
+    let mut sessions = state.0.lock().unwrap();
+    match sessions.get_mut(token) {
+        Some(session) => {
+            let now = Utc::now();
+            session.enc_key = enc_key;
+            session.locked = false;
+            session.last_activity = now;
+            session.verified_at = now;
+            Ok(())
+        }
+        None => Err("Not authenticated".to_string()),
+    }
+}
+
+/// Locks every unlocked session that has been idle for longer than `idle_minutes`
+/// and returns the sessions that were locked.
+pub fn lock_idle_sessions(registry: &SessionRegistry, idle_minutes: i64) -> Vec<Session> {
+    let cutoff = Utc::now() - Duration::minutes(idle_minutes);
+    let mut locked = Vec::new();
+
+    for session in registry.0.lock().unwrap().values_mut() {
+        if !session.locked && session.last_activity < cutoff {
+            discard_key(session);
+            locked.push(session.clone());
+        }
+    }
+
+    locked
+}
+
+/// Ends every session that logged in more than `lifetime_hours` ago, locked or
+/// not, and returns the sessions that were ended.
+pub fn expire_sessions(registry: &SessionRegistry, lifetime_hours: i64) -> Vec<Session> {
+    let cutoff = Utc::now() - Duration::hours(lifetime_hours);
+    let mut sessions = registry.0.lock().unwrap();
+
+    let expired: Vec<String> = sessions
+        .iter()
+        .filter(|(_, session)| session.created_at < cutoff)
+        .map(|(token, _)| token.clone())
+        .collect();
+
+    expired
+        .iter()
+        .filter_map(|token| sessions.remove(token))
+        .map(|mut session| {
+            discard_key(&mut session);
+            session
+        })
+        .collect()
+}
+
+pub async fn run_auto_lock(sessions: SessionRegistry, pool: DatabasePool, idle_minutes: i64) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
+
+    loop {
+        interval.tick().await;
+
+        for session in lock_idle_sessions(&sessions, idle_minutes) {
+            record_background_event(
+                &pool,
+                &session,
+                audit::VAULT_LOCKED,
+                "Locked after inactivity",
+            )
+            .await;
+        }
+    }
+}
+
+pub async fn run_expiry(sessions: SessionRegistry, pool: DatabasePool, lifetime_hours: i64) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+
+    loop {
+        interval.tick().await;
+
+        for session in expire_sessions(&sessions, lifetime_hours) {
+            record_background_event(&pool, &session, audit::LOGOUT, "Session expired").await;
+        }
+    }
+}
+
+/// Audits something a background task did to a session. There is no caller to
+/// return a failure to, so it goes to stderr.
+async fn record_background_event(
+    pool: &DatabasePool,
+    session: &Session,
+    event: &str,
+    details: &str,
+) {
+    // The vault may have been closed since
+    let Ok(db) = pool.for_session(session) else {
+        return;
+    };
+
+    if let Err(e) = audit::record_event(
+        &*db,
+        Some(&session.user_id),
+        None,
+        event,
+        Some(session.source),
+        Some(details),
+    )
+    .await
+    {
+        eprintln!("Failed to write audit record: {}", e);
+    }
+}
+
+fn discard_key(session: &mut Session) {
+    let mut key_bytes = std::mem::take(&mut session.enc_key).into_bytes();
+    key_bytes.fill(0);
+    session.locked = true;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(age: Duration, idle: Duration) -> Session {
+        let now = Utc::now();
+        Session {
+            vault_path: PathBuf::from("/vault.db"),
+            user_id: "user".into(),
+            enc_key: "key".into(),
+            locked: false,
+            scope: SessionScope::Full,
+            source: SessionSource::Window,
+            previous_login_at: None,
+            created_at: now - age,
+            last_activity: now - idle,
+            verified_at: now - idle,
+        }
+    }
+
+    fn registry(sessions: &[(&str, Session)]) -> SessionRegistry {
+        let registry = SessionRegistry::default();
+        registry.0.lock().unwrap().extend(
+            sessions
+                .iter()
+                .map(|(token, session)| (token.to_string(), session.clone())),
+        );
+        registry
+    }
+
+    #[test]
+    fn expires_sessions_past_their_lifetime() {
+        let registry = registry(&[
+            ("old", session(Duration::hours(25), Duration::zero())),
+            ("new", session(Duration::hours(1), Duration::zero())),
+        ]);
+
+        let expired = expire_sessions(&registry, 24);
+
+        assert_eq!(expired.len(), 1);
+        assert!(expired[0].enc_key.is_empty());
+        let sessions = registry.0.lock().unwrap();
+        assert!(!sessions.contains_key("old"));
+        assert!(sessions.contains_key("new"));
+    }
+
+    #[test]
+    fn expires_locked_sessions_too() {
+        let mut locked = session(Duration::hours(48), Duration::hours(47));
+        discard_key(&mut locked);
+        let registry = registry(&[("locked", locked)]);
+
+        assert_eq!(expire_sessions(&registry, 24).len(), 1);
+        assert!(registry.0.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn locks_only_idle_sessions() {
+        let registry = registry(&[
+            ("idle", session(Duration::hours(1), Duration::minutes(20))),
+            ("active", session(Duration::hours(1), Duration::minutes(1))),
+        ]);
+
+        let locked = lock_idle_sessions(&registry, 15);
+
+        assert_eq!(locked.len(), 1);
+        let sessions = registry.0.lock().unwrap();
+        assert!(sessions["idle"].locked);
+        assert!(sessions["idle"].enc_key.is_empty());
+        assert!(!sessions["active"].locked);
+        assert_eq!(sessions["active"].enc_key, "key");
+    }
+}
@@ -0,0 +1,39 @@
+use crate::DatabasePool;
+use chrono::{Duration, Utc};
+use sqlx::SqliteExecutor;
+
+/// Permanently deletes entries that have been in the trash for longer than
+/// `retention_days`, returning how many were removed.
+pub async fn purge_expired<'e, E>(executor: E, retention_days: i64) -> Result<u64, String>
+where
+    E: SqliteExecutor<'e>,
+{
+    let cutoff = Utc::now() - Duration::days(retention_days);
+
+    // Custom fields, URLs, history and attachments go through ON DELETE CASCADE
+    let result =
+        sqlx::query("DELETE FROM passwords WHERE deleted_at IS NOT NULL AND deleted_at < ?")
+            .bind(cutoff)
+            .execute(executor)
+            .await
+            .map_err(|e| format!("Failed to purge trash: {}", e))?;
+
+    Ok(result.rows_affected())
+}
+
+/// Purges the trash of every open vault each hour, starting right away.
+pub async fn run_purge(pool: DatabasePool, retention_days: i64) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(60 * 60));
+
+    loop {
+        interval.tick().await;
+
+        let vaults: Vec<_> = pool.0.read().unwrap().open.values().cloned().collect();
+
+        for db in vaults {
+            if let Err(e) = purge_expired(&*db, retention_days).await {
+                eprintln!("{}", e);
+            }
+        }
+    }
+}
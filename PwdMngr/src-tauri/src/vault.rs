@@ -0,0 +1,155 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const RECENT_VAULTS_FILE: &str = "recent_vaults.json";
+const MAX_RECENT_VAULTS: usize = 10;
+
+/// Every vault file currently open, and the one commands run against.
+#[derive(Default)]
+pub struct Vaults {
+    pub open: HashMap<PathBuf, Arc<SqlitePool>>,
+    pub active: Option<PathBuf>,
+}
+
+impl Vaults {
+    pub fn with_active(path: PathBuf, pool: SqlitePool) -> Self {
+        let mut open = HashMap::new();
+        open.insert(path.clone(), Arc::new(pool));
+
+        Self {
+            open,
+            active: Some(path),
+        }
+    }
+
+    /// Makes a vault the active one, adding its pool if it was not open yet.
+    /// Only new logins go to the active vault; existing sessions stay bound to
+    /// the vault they logged into.
+    pub fn activate(&mut self, path: PathBuf, pool: Option<SqlitePool>) {
+        if let Some(pool) = pool {
+            self.open.insert(path.clone(), Arc::new(pool));
+        }
+
+        self.active = Some(path);
+    }
+
+    /// Removes a vault that is not the active one and returns its pool.
+    /// The active vault stays open so there is always one to log into.
+    pub fn close(&mut self, path: &Path) -> Result<Arc<SqlitePool>, String> {
+        if self.active.as_deref() == Some(path) {
+            return Err("Switch to another vault before closing this one".into());
+        }
+
+        self.open
+            .remove(path)
+            .ok_or_else(|| "Vault is not open".to_string())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentVault {
+    pub path: String,
+    pub last_opened: DateTime<Utc>,
+}
+
+pub fn load_recent_vaults(data_dir: &Path) -> Vec<RecentVault> {
+    std::fs::read_to_string(data_dir.join(RECENT_VAULTS_FILE))
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+pub fn remember_vault(data_dir: &Path, vault_path: &Path) -> Result<(), String> {
+    let vault_path = vault_path.display().to_string();
+
+    let mut recent = load_recent_vaults(data_dir);
+    recent.retain(|vault| vault.path != vault_path);
+    recent.insert(
+        0,
+        RecentVault {
+            path: vault_path,
+            last_opened: Utc::now(),
+        },
+    );
+    recent.truncate(MAX_RECENT_VAULTS);
+
+    let contents = serde_json::to_string_pretty(&recent)
+        .map_err(|e| format!("Failed to save recent vaults: {}", e))?;
+    std::fs::write(data_dir.join(RECENT_VAULTS_FILE), contents)
+        .map_err(|e| format!("Failed to save recent vaults: {}", e))
+}
+
+/// Resolves a user supplied vault path to an absolute one so the same file
+/// is never opened twice under different names.
+pub fn resolve_vault_path(path: &str, must_exist: bool) -> Result<PathBuf, String> {
+    if path.trim().is_empty() {
+        return Err("Vault path cannot be empty".into());
+    }
+
+    let path = Path::new(path);
+    if must_exist {
+        return path
+            .canonicalize()
+            .map_err(|e| format!("Vault {} not found: {}", path.display(), e));
+    }
+
+    let file_name = path.file_name().ok_or("Vault path must name a file")?;
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let parent = parent
+        .canonicalize()
+        .map_err(|e| format!("Vault directory {} not found: {}", parent.display(), e))?;
+
+    Ok(parent.join(file_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool() -> SqlitePool {
+        SqlitePool::connect_lazy("sqlite::memory:").unwrap()
+    }
+
+    #[tokio::test]
+    async fn refuses_to_close_the_active_vault() {
+        let mut vaults = Vaults::with_active(PathBuf::from("/a.db"), pool());
+
+        assert!(vaults.close(Path::new("/a.db")).is_err());
+        assert!(vaults.open.contains_key(Path::new("/a.db")));
+        assert_eq!(vaults.active, Some(PathBuf::from("/a.db")));
+    }
+
+    #[tokio::test]
+    async fn closed_vault_can_be_reopened() {
+        let mut vaults = Vaults::with_active(PathBuf::from("/a.db"), pool());
+        vaults.activate(PathBuf::from("/b.db"), Some(pool()));
+
+        vaults.close(Path::new("/a.db")).unwrap();
+        assert!(!vaults.open.contains_key(Path::new("/a.db")));
+        assert!(vaults.close(Path::new("/a.db")).is_err());
+
+        vaults.activate(PathBuf::from("/a.db"), Some(pool()));
+        assert_eq!(vaults.active, Some(PathBuf::from("/a.db")));
+
+        vaults.close(Path::new("/b.db")).unwrap();
+        assert_eq!(vaults.open.len(), 1);
+        assert!(vaults.close(Path::new("/a.db")).is_err());
+    }
+
+    #[tokio::test]
+    async fn switching_keeps_the_pool() {
+        let mut vaults = Vaults::with_active(PathBuf::from("/a.db"), pool());
+        vaults.activate(PathBuf::from("/b.db"), Some(pool()));
+        vaults.activate(PathBuf::from("/a.db"), None);
+
+        assert_eq!(vaults.active, Some(PathBuf::from("/a.db")));
+        assert_eq!(vaults.open.len(), 2);
+    }
+}
@@ -25,11 +25,11 @@ function showSuccess(message) {
 }
 
 async function addNewPassword() {
-    if (!sessionStorage.getItem("encKey")) {
+    if (!sessionStorage.getItem("sessionToken")) {
         return (location.href = "/login.html");
     }
 
-    const encKey = sessionStorage.getItem("encKey");
+    const sessionToken = sessionStorage.getItem("sessionToken");
 
     const website = document.getElementById("websiteInput").value.trim();
     const website_url = document.getElementById("websiteUrlInput").value.trim();
@@ -58,7 +58,7 @@ async function addNewPassword() {
         website,
         username,
         password,
-        encKey,
+        sessionToken,
     };
 
     if (website_url) params.websiteUrl = website_url;
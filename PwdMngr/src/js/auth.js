@@ -41,14 +41,19 @@ async function loginUser() {
             username,
             password,
         });
-        if (response.encKey) {
-            sessionStorage.setItem("encKey", response.encKey);
-            showSuccess(response.message || "Login successful!");
+        if (response.sessionToken) {
+            sessionStorage.setItem("sessionToken", response.sessionToken);
+            const failedAttempts = response.failed_attempts_since_last_login;
+            showSuccess(
+                failedAttempts > 0
+                    ? `${response.message} ${failedAttempts} failed login attempt(s) since your last login.`
+                    : response.message || "Login successful!",
+            );
             setTimeout(() => {
                 window.location.href = "/index.html";
             }, 1500);
         } else {
-            showError("Login successful but failed to get encryption key");
+            showError("Login successful but failed to start a session");
         }
     } catch (error) {
         showError(error.toString());
@@ -81,19 +86,19 @@ async function registerUser() {
             password,
             confirmPassword,
         });
-        if (response.encKey) {
-            sessionStorage.setItem("encKey", response.encKey);
+        if (response.sessionToken) {
+            sessionStorage.setItem("sessionToken", response.sessionToken);
             showSuccess(response.message || "Registration successful!");
             setTimeout(() => {
                 window.location.href = "/index.html";
             }, 1500);
         } else {
             showError(
-                "Registration successful but failed to get encryption key",
+                "Registration successful but failed to start a session",
             );
         }
     } catch (error) {
-        showError(error.toString());
+        showError(error?.message ?? error.toString());
     } finally {
         submitBtn.disabled = false;
         submitBtn.textContent = "Register";
@@ -101,7 +106,7 @@ async function registerUser() {
 }
 
 document.addEventListener("DOMContentLoaded", function () {
-    if (sessionStorage.getItem("encKey")) return (location.href = "/");
+    if (sessionStorage.getItem("sessionToken")) return (location.href = "/");
 
     const usernameInput = document.getElementById("usernameInput");
     const passwordInput = document.getElementById("passwordInput");
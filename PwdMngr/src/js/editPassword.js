@@ -27,7 +27,7 @@ function showSuccess(message) {
 }
 
 document.addEventListener("DOMContentLoaded", async function () {
-    if (!sessionStorage.getItem("encKey")) {
+    if (!sessionStorage.getItem("sessionToken")) {
         return (location.href = "/login.html");
     }
 
@@ -57,10 +57,10 @@ document.addEventListener("DOMContentLoaded", async function () {
 
 async function loadPasswordData() {
     try {
-        const encKey = sessionStorage.getItem("encKey");
+        const sessionToken = sessionStorage.getItem("sessionToken");
         const response = await invoke("get_password_details", {
             id: passwordId,
-            encKey,
+            sessionToken,
         });
 
         if (response) {
@@ -90,11 +90,11 @@ async function loadPasswordData() {
 }
 
 async function updatePassword() {
-    if (!sessionStorage.getItem("encKey")) {
+    if (!sessionStorage.getItem("sessionToken")) {
         return (location.href = "/login.html");
     }
 
-    const encKey = sessionStorage.getItem("encKey");
+    const sessionToken = sessionStorage.getItem("sessionToken");
 
     const website = document.getElementById("websiteInput").value.trim();
     const website_url = document.getElementById("websiteUrlInput").value.trim();
@@ -124,7 +124,7 @@ async function updatePassword() {
         website,
         username,
         password,
-        encKey,
+        sessionToken,
     };
 
     if (website_url) params.websiteUrl = website_url;
@@ -174,7 +174,8 @@ async function deletePassword() {
         .querySelector(".confirm-delete")
         .addEventListener("click", async () => {
             try {
-                const result = await invoke("delete_password", {
+                const result = await invokeWithReauth("delete_password", {
+                    sessionToken: sessionStorage.getItem("sessionToken"),
                     id: passwordId,
                 });
                 if (result.message) {
@@ -11,7 +11,7 @@ let allPasswords = [];
 
 document.addEventListener("DOMContentLoaded", function () {
     // Check authentication
-    if (!sessionStorage.getItem("encKey")) {
+    if (!sessionStorage.getItem("sessionToken")) {
         window.location.href = "/login.html";
         return;
     }
@@ -53,9 +53,9 @@ function switchTab(tab) {
 // Load all passwords for export
 async function loadAllPasswordsForExport() {
     try {
-        const encKey = sessionStorage.getItem("encKey");
-        if (!encKey) {
-            throw new Error("Authentication key not found");
+        const sessionToken = sessionStorage.getItem("sessionToken");
+        if (!sessionToken) {
+            throw new Error("Session not found");
         }
 
         // Show loading indicator
@@ -63,8 +63,8 @@ async function loadAllPasswordsForExport() {
         passwordList.innerHTML =
             '<div class="ei-loading">Loading passwords...</div>';
 
-        const response = await invoke("get_all_passwords_for_export", {
-            encKey,
+        const response = await invokeWithReauth("get_all_passwords_for_export", {
+            sessionToken,
         });
 
         if (!response || !response.passwords) {
@@ -176,9 +176,9 @@ async function exportPasswords() {
         showStatus(`Preparing export... Please wait.`, "success");
 
         // Get decrypted password data from backend
-        const encKey = sessionStorage.getItem("encKey");
-        const response = await invoke("prepare_passwords_for_export", {
-            encKey,
+        const sessionToken = sessionStorage.getItem("sessionToken");
+        const response = await invokeWithReauth("prepare_passwords_for_export", {
+            sessionToken,
             selectedIds,
         });
 
@@ -602,8 +602,8 @@ async function importPasswords() {
         return;
     }
 
-    const encKey = sessionStorage.getItem("encKey");
-    if (!encKey) {
+    const sessionToken = sessionStorage.getItem("sessionToken");
+    if (!sessionToken) {
         showStatus("Authentication error. Please log in again.", "error");
         setTimeout(() => {
             window.location.href = "/login.html";
@@ -625,7 +625,7 @@ async function importPasswords() {
     try {
         const result = await invoke("import_passwords_from_data", {
             passwordsData: passwords,
-            encKey,
+            sessionToken,
         });
 
         if (result.success) {
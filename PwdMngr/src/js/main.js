@@ -2,7 +2,7 @@ const { invoke } = window.__TAURI__.core;
 const { openUrl } = window.__TAURI__.opener;
 const { writeText } = window.__TAURI__.clipboardManager;
 
-if (!sessionStorage.getItem("encKey")) {
+if (!sessionStorage.getItem("sessionToken")) {
     window.location.href = "/login.html";
 }
 
@@ -71,8 +71,8 @@ async function loadPasswords(page) {
         const passwordList = document.getElementById("passwordList");
         passwordList.innerHTML = "";
 
-        const encKey = sessionStorage.getItem("encKey");
-        const response = await invoke("get_passwords", { page, encKey });
+        const sessionToken = sessionStorage.getItem("sessionToken");
+        const response = await invoke("get_passwords", { page, sessionToken });
 
         currentPage = response.page;
         totalPages = response.total_pages;
@@ -139,10 +139,37 @@ async function loadPasswords(page) {
             "#pagination p",
         ).textContent = `${currentPage} of ${totalPages}`;
     } catch (error) {
+        if (await unlockIfLocked(error)) {
+            return loadPasswords(page);
+        }
         console.error("Failed to load passwords:", error);
     }
 }
 
+async function unlockIfLocked(error) {
+    if (error !== "Vault is locked") {
+        return false;
+    }
+
+    const password = prompt(
+        "Your vault is locked. Enter your master password to unlock it.",
+    );
+    if (!password) {
+        return false;
+    }
+
+    try {
+        await invoke("unlock_vault", {
+            sessionToken: sessionStorage.getItem("sessionToken"),
+            password,
+        });
+        return true;
+    } catch (unlockError) {
+        console.error("Failed to unlock vault:", unlockError);
+        return false;
+    }
+}
+
 async function performSearch(page = 1) {
     const searchInput = document.getElementById("searchInput");
     const searchTerm = searchInput.value.trim();
@@ -164,11 +191,11 @@ async function performSearch(page = 1) {
         const passwordList = document.getElementById("passwordList");
         passwordList.innerHTML = "";
 
-        const encKey = sessionStorage.getItem("encKey");
+        const sessionToken = sessionStorage.getItem("sessionToken");
         const response = await invoke("search_passwords", {
             searchTerm: searchTerm,
             page: page,
-            encKey: encKey,
+            sessionToken: sessionToken,
         });
 
         currentPage = response.page;
@@ -271,6 +298,10 @@ async function performSearch(page = 1) {
             passwordList.appendChild(passwordCard);
         });
     } catch (error) {
+        if (await unlockIfLocked(error)) {
+            lastSearchTerm = "";
+            return performSearch(page);
+        }
         console.error("Search failed:", error);
         const passwordList = document.getElementById("passwordList");
         passwordList.innerHTML = `<div class="error-message">Search failed: ${error}</div>`;
@@ -282,8 +313,9 @@ function escapeRegExp(string) {
 }
 
 function logout() {
-    sessionStorage.removeItem("encKey");
-    invoke("logout_user");
+    const sessionToken = sessionStorage.getItem("sessionToken");
+    sessionStorage.removeItem("sessionToken");
+    invoke("logout_user", { sessionToken });
     window.location.href = "/login.html";
 }
 
@@ -0,0 +1,30 @@
+// Shared by the pages that run sensitive commands; load it before the page script.
+
+// Sensitive commands fail with a ReauthRequired error when the master password
+// has not been entered recently; ask for it once and retry the command.
+async function invokeWithReauth(command, args) {
+    const { invoke } = window.__TAURI__.core;
+
+    try {
+        return await invoke(command, args);
+    } catch (error) {
+        if (error?.kind !== "ReauthRequired") {
+            throw error?.message ?? error;
+        }
+
+        const password = prompt(error.message);
+        if (!password) {
+            throw error.message;
+        }
+
+        try {
+            await invoke("verify_master_password", {
+                sessionToken: sessionStorage.getItem("sessionToken"),
+                password,
+            });
+            return await invoke(command, args);
+        } catch (retryError) {
+            throw retryError?.message ?? retryError;
+        }
+    }
+}